@@ -0,0 +1,315 @@
+use super::calc::ScorePool;
+use super::forecast::CutoffForecast;
+use super::rate::{RateAnalyzer, RateConfig};
+use crate::data::{CategoryCode, Invite, Pool};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A prediction made with the data before `date`, and what happened.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BacktestSample<K> {
+    pub key: K,
+    pub date: NaiveDate,
+    pub actual: f64,
+    pub predicted: f64,
+    /// the interval of the prediction, if any
+    pub interval: Option<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ErrorMetrics {
+    pub count: usize,
+    /// mean absolute error
+    pub mae: f64,
+    /// root mean square error
+    pub rmse: f64,
+    /// share of the samples within their interval, if they have one
+    pub coverage: Option<f64>,
+}
+
+impl ErrorMetrics {
+    pub fn of<K>(samples: &[BacktestSample<K>]) -> Self {
+        let n = samples.len().max(1) as f64;
+        let errors = || {
+            samples
+                .iter()
+                .map(|sample| sample.predicted - sample.actual)
+        };
+        let intervals: Vec<_> = samples
+            .iter()
+            .filter_map(|sample| Some((sample.interval?, sample.actual)))
+            .collect();
+        Self {
+            count: samples.len(),
+            mae: errors().map(f64::abs).sum::<f64>() / n,
+            rmse: (errors().map(|e| e * e).sum::<f64>() / n).sqrt(),
+            coverage: (!intervals.is_empty()).then(|| {
+                let within = intervals
+                    .iter()
+                    .filter(|((low, high), actual)| (*low..=*high).contains(actual))
+                    .count();
+                within as f64 / intervals.len() as f64
+            }),
+        }
+    }
+
+    /// `ErrorMetrics` of the samples of each key.
+    pub fn per_key<K: Ord + Copy>(samples: &[BacktestSample<K>]) -> BTreeMap<K, Self> {
+        let mut groups: BTreeMap<K, Vec<BacktestSample<K>>> = BTreeMap::new();
+        for sample in samples {
+            groups.entry(sample.key).or_default().push(*sample);
+        }
+        groups
+            .into_iter()
+            .map(|(key, samples)| (key, Self::of(&samples)))
+            .collect()
+    }
+}
+
+pub struct BacktestAnalyzer;
+
+impl BacktestAnalyzer {
+    /// The data published before `date`, both sorted by date: the draws
+    /// before it, and the pools those draws published.
+    pub fn before<'a>(
+        pool_data: &[Pool],
+        invite_data: &'a [Invite],
+        date: NaiveDate,
+    ) -> (Vec<Pool>, &'a [Invite]) {
+        (
+            pool_data
+                .iter()
+                .filter(|pool| pool.published < date)
+                .copied()
+                .collect(),
+            &invite_data[..invite_data.partition_point(|invite| invite.date < date)],
+        )
+    }
+
+    /// Replays the draws since `since`: `forecaster` sees the data before
+    /// each draw, and its forecast of the category of the draw is compared
+    /// with the cutoff. Draws the forecaster has no forecast for are skipped.
+    pub fn cutoffs(
+        pool_data: &[Pool],
+        invite_data: &[Invite],
+        since: NaiveDate,
+        forecaster: impl Fn(&[Pool], &[Invite]) -> Vec<CutoffForecast>,
+    ) -> Vec<BacktestSample<CategoryCode>> {
+        let mut samples = Vec::new();
+        let mut forecasts: Option<(NaiveDate, Vec<CutoffForecast>)> = None;
+        for invite in invite_data.iter().filter(|invite| invite.date >= since) {
+            // draws of the same day share the data before them
+            if forecasts.as_ref().map(|(date, _)| *date) != Some(invite.date) {
+                let (pool_data, invite_data) = Self::before(pool_data, invite_data, invite.date);
+                forecasts = Some((invite.date, forecaster(&pool_data, invite_data)));
+            }
+            let (_, forecasts) = forecasts.as_ref().unwrap();
+            let Some(forecast) = forecasts
+                .iter()
+                .find(|forecast| forecast.code == invite.category.code)
+            else {
+                continue;
+            };
+            samples.push(BacktestSample {
+                key: invite.category.code,
+                date: invite.date,
+                actual: invite.score,
+                predicted: forecast.score.mid,
+                interval: Some((forecast.score.low, forecast.score.high)),
+            });
+        }
+        samples
+    }
+
+    /// Replays the pools since `since`: each bucket of a pool is predicted
+    /// from the pool before, grown by `RateAnalyzer::projected_rate` of the
+    /// data before, less the invitations in between.
+    pub fn rates(
+        pool_data: &[Pool],
+        invite_data: &[Invite],
+        since: NaiveDate,
+        config: &RateConfig,
+    ) -> Vec<BacktestSample<usize>> {
+        let pool_data: Vec<_> = pool_data
+            .iter()
+            .filter(|pool| pool.is_consistent())
+            .copied()
+            .collect();
+
+        let mut samples = Vec::new();
+        for pools in pool_data.windows(2) {
+            let (pool0, pool1) = (pools[0], pools[1]);
+            if pool1.date < since {
+                continue;
+            }
+            let (known_pools, known_invites) = Self::before(&pool_data, invite_data, pool1.date);
+            let (_, rate_data) =
+                RateAnalyzer::pool_increase_rate(&known_pools, known_invites, config);
+            let rate = RateAnalyzer::projected_rate(&rate_data, config);
+
+            let days = (pool1.date - pool0.date).num_days() as f64;
+            let mut predicted = ScorePool::from(pool0) + rate * days;
+            let mut pool_to_invite = ScorePool::from(pool0);
+            for invite in known_invites
+                .iter()
+                .filter(|invite| invite.date >= pool0.date)
+            {
+                let invited = pool_to_invite.invite(invite);
+                pool_to_invite = pool_to_invite - invited;
+                predicted = predicted - invited;
+            }
+
+            let actual = ScorePool::from(pool1);
+            for i in 0..actual.len() {
+                samples.push(BacktestSample {
+                    key: i,
+                    date: pool1.date,
+                    actual: actual[i],
+                    predicted: predicted[i],
+                    interval: None,
+                });
+            }
+        }
+        samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BacktestAnalyzer, BacktestSample, ErrorMetrics};
+    use crate::analyze::calc::ScoreDensity;
+    use crate::analyze::forecast::{Band, CutoffForecast, ForecastAnalyzer};
+    use crate::analyze::rate::RateConfig;
+    use crate::analyze::simulate::SimulationConfig;
+    use crate::data::{test_raw_data, CategoryCode, Invite, Pool};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn error_metrics() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let sample = |key, actual, predicted, interval| BacktestSample {
+            key,
+            date,
+            actual,
+            predicted,
+            interval,
+        };
+        let samples = [
+            sample(0, 500.0, 510.0, Some((490.0, 520.0))),
+            sample(0, 500.0, 480.0, Some((470.0, 490.0))),
+            sample(1, 400.0, 400.0, None),
+        ];
+
+        let metrics = ErrorMetrics::per_key(&samples);
+        assert_eq!(metrics[&0].count, 2);
+        assert_eq!(metrics[&0].mae, 15.0);
+        assert_eq!(metrics[&0].rmse, 250_f64.sqrt());
+        assert_eq!(metrics[&0].coverage, Some(0.5));
+        assert_eq!(metrics[&1].mae, 0.0);
+        assert_eq!(metrics[&1].coverage, None);
+        assert_eq!(ErrorMetrics::of::<usize>(&[]).count, 0);
+    }
+
+    #[tokio::test]
+    async fn cutoffs() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let since = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        // the distribution published with a draw is dated before it, but
+        // only known from the draw on
+        let own = pool_data.last().unwrap();
+        assert!(own.date < own.published);
+        let (pools, _) = BacktestAnalyzer::before(&pool_data, &invite_data, own.published);
+        assert!(!pools.contains(own));
+        assert!(pools.iter().all(|pool| pool.published < own.published));
+
+        // the cutoff of the latest draw of each category, give or take 10
+        let naive = |_: &[Pool], invite_data: &[Invite]| {
+            let mut latest: Vec<CutoffForecast> = Vec::new();
+            for invite in invite_data.iter().rev() {
+                if latest.iter().all(|x| x.code != invite.category.code) {
+                    latest.push(CutoffForecast {
+                        code: invite.category.code,
+                        category: invite.category.code.key(),
+                        label: invite.category.as_str(),
+                        date: invite.date,
+                        score: Band {
+                            low: invite.score - 10.0,
+                            mid: invite.score,
+                            high: invite.score + 10.0,
+                        },
+                        size: Band {
+                            low: invite.size,
+                            mid: invite.size,
+                            high: invite.size,
+                        },
+                        likelihood: 1.0,
+                    });
+                }
+            }
+            latest
+        };
+        let samples = BacktestAnalyzer::cutoffs(&pool_data, &invite_data, since, naive);
+        assert!(samples.iter().all(|sample| sample.date >= since));
+        let general: Vec<_> = invite_data
+            .iter()
+            .filter(|invite| invite.date >= since)
+            .filter(|invite| invite.category.code == CategoryCode::GENERAL)
+            .collect();
+        let metrics = ErrorMetrics::per_key(&samples);
+        assert_eq!(metrics[&CategoryCode::GENERAL].count, general.len());
+
+        // the simulation on the recent draws, with the data before each
+        let since = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let rate_config = RateConfig::default();
+        let config = SimulationConfig {
+            runs: 20,
+            ..Default::default()
+        };
+        let samples = BacktestAnalyzer::cutoffs(&pool_data, &invite_data, since, |p, i| {
+            ForecastAnalyzer::next_draws(p, i, ScoreDensity::Uniform, &rate_config, &config)
+        });
+        assert!(!samples.is_empty());
+        for metrics in ErrorMetrics::per_key(&samples).values() {
+            assert!(metrics.mae <= metrics.rmse + 1e-9);
+            assert!((0.0..=1.0).contains(&metrics.coverage.unwrap()));
+        }
+    }
+
+    #[tokio::test]
+    async fn rates() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let since = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        let (pools, invites) = BacktestAnalyzer::before(&pool_data, &invite_data, since);
+        assert!(pools.iter().all(|pool| pool.published < since));
+        assert!(invites.iter().all(|invite| invite.date < since));
+        assert_eq!(
+            pools.len(),
+            pool_data
+                .iter()
+                .filter(|pool| pool.published < since)
+                .count()
+        );
+
+        let samples =
+            BacktestAnalyzer::rates(&pool_data, &invite_data, since, &RateConfig::default());
+        let metrics = ErrorMetrics::per_key(&samples);
+        assert_eq!(metrics.len(), pool_data[0].len());
+        assert!(metrics.values().all(|m| m.count > 0 && m.rmse.is_finite()));
+        // the misses are a fraction of a bucket, and bounded by the worst one
+        assert!(metrics
+            .values()
+            .all(|m| m.mae <= m.rmse && m.rmse < 10000.0));
+        // one sample per bucket of every pool since, except the first pool
+        let pools_since = pool_data
+            .iter()
+            .filter(|pool| pool.is_consistent() && pool.date >= since)
+            .count();
+        assert_eq!(samples.len(), pools_since * pool_data[0].len());
+        assert!(samples.iter().all(|sample| sample.date >= since));
+    }
+}
@@ -25,13 +25,13 @@ impl Default for CategoryPool {
 impl Index<CategoryCode> for CategoryPool {
     type Output = f64;
     fn index(&self, i: CategoryCode) -> &Self::Output {
-        &self.0[i as usize]
+        &self.0[i.index()]
     }
 }
 
 impl IndexMut<CategoryCode> for CategoryPool {
     fn index_mut(&mut self, i: CategoryCode) -> &mut Self::Output {
-        &mut self.0[i as usize]
+        &mut self.0[i.index()]
     }
 }
 
@@ -46,8 +46,8 @@ impl Add for CategoryPool {
     type Output = Self;
 
     fn add(mut self, rhs: Self) -> Self::Output {
-        for i in CategoryCode::values().iter().copied() {
-            self[i] = self[i] + rhs[i];
+        for i in 0..Self::N {
+            self.0[i] += rhs.0[i];
         }
         self
     }
@@ -57,8 +57,8 @@ impl Sub for CategoryPool {
     type Output = Self;
 
     fn sub(mut self, rhs: Self) -> Self::Output {
-        for i in CategoryCode::values().iter().copied() {
-            self[i] = self[i] - rhs[i];
+        for i in 0..Self::N {
+            self.0[i] -= rhs.0[i];
         }
         self
     }
@@ -68,8 +68,8 @@ impl Mul for CategoryPool {
     type Output = Self;
 
     fn mul(mut self, rhs: Self) -> Self::Output {
-        for i in CategoryCode::values().iter().copied() {
-            self[i] = self[i] * rhs[i];
+        for i in 0..Self::N {
+            self.0[i] *= rhs.0[i];
         }
         self
     }
@@ -79,8 +79,8 @@ impl Div for CategoryPool {
     type Output = Self;
 
     fn div(mut self, rhs: Self) -> Self::Output {
-        for i in CategoryCode::values().iter().copied() {
-            self[i] = self[i] / rhs[i];
+        for i in 0..Self::N {
+            self.0[i] /= rhs.0[i];
         }
         self
     }
@@ -90,8 +90,8 @@ impl Mul<f64> for CategoryPool {
     type Output = Self;
 
     fn mul(mut self, rhs: f64) -> Self::Output {
-        for i in CategoryCode::values().iter().copied() {
-            self[i] = self[i] * rhs;
+        for i in 0..Self::N {
+            self.0[i] *= rhs;
         }
         self
     }
@@ -101,8 +101,8 @@ impl Div<f64> for CategoryPool {
     type Output = Self;
 
     fn div(mut self, rhs: f64) -> Self::Output {
-        for i in CategoryCode::values().iter().copied() {
-            self[i] = self[i] / rhs;
+        for i in 0..Self::N {
+            self.0[i] /= rhs;
         }
         self
     }
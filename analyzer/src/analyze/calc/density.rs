@@ -0,0 +1,118 @@
+use crate::data::BucketSchema;
+
+/// How candidates spread over the scores within a bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ScoreDensity {
+    /// evenly over the range of the bucket
+    #[default]
+    Uniform,
+    /// A linear density in each bucket, sloped towards the denser neighbor
+    /// bucket and keeping the count of the bucket. `steepness` scales the
+    /// slope from 0 (uniform) to 1 (as steep as the density stays positive).
+    Linear { steepness: f64 },
+}
+
+impl ScoreDensity {
+    /// Share of bucket `i` of `data`, laid out in `schema`, between
+    /// `min_score` and `max_score`.
+    pub fn fraction_within(
+        &self,
+        schema: &BucketSchema,
+        data: &[f64],
+        i: usize,
+        min_score: f64,
+        max_score: f64,
+    ) -> f64 {
+        let steepness = match self {
+            Self::Uniform => return schema.fraction_within(i, min_score, max_score),
+            Self::Linear { steepness } => *steepness,
+        };
+        if !data[i].is_finite() || data[i] <= 0.0 {
+            return schema.fraction_within(i, min_score, max_score);
+        }
+
+        let (min_d, max_d) = (schema.min_score(i) as f64, schema.max_score(i) as f64);
+        let mid = (min_d + max_d) / 2.0;
+        let u = f64::min(f64::max(min_score, min_d), max_d);
+        let v = f64::min(f64::max(max_score, min_d), max_d);
+        if u >= v {
+            return 0.0;
+        }
+
+        let density = data[i] / (max_d - min_d);
+        let slope = Self::slope(schema, data, i) * steepness;
+        let integral = density * (v - u) + slope / 2.0 * ((v - mid).powi(2) - (u - mid).powi(2));
+        integral / data[i]
+    }
+
+    // the difference of the densities of the neighbor buckets, limited so
+    // the density stays positive over bucket `i`
+    fn slope(schema: &BucketSchema, data: &[f64], i: usize) -> f64 {
+        let density = |j: usize| data[j] / (schema.max_score(j) - schema.min_score(j)) as f64;
+        let mid = |j: usize| (schema.min_score(j) + schema.max_score(j)) as f64 / 2.0;
+
+        let lo = i.saturating_sub(1);
+        let hi = usize::min(i + 1, schema.len() - 1);
+        if lo == hi {
+            return 0.0;
+        }
+        let slope = (density(hi) - density(lo)) / (mid(hi) - mid(lo));
+        if !slope.is_finite() {
+            // a neighbor without a usable count, the bucket stays flat
+            return 0.0;
+        }
+
+        let limit = 2.0 * density(i) / (schema.max_score(i) - schema.min_score(i)) as f64;
+        f64::min(f64::max(slope, -limit), limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScoreDensity;
+    use crate::data::BucketSchema;
+
+    #[test]
+    fn fraction_within() {
+        let schema = &BucketSchema::CURRENT;
+        let data = [
+            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 1400.0, 15.0,
+        ];
+        let uniform = ScoreDensity::Uniform;
+        let linear = ScoreDensity::Linear { steepness: 1.0 };
+
+        for i in 0..schema.len() {
+            let whole = linear.fraction_within(schema, &data, i, 0.0, 1200.0);
+            assert!((whole - 1.0).abs() < 1e-9);
+            assert_eq!(
+                ScoreDensity::Linear { steepness: 0.0 }
+                    .fraction_within(schema, &data, i, 0.0, 520.0),
+                uniform.fraction_within(schema, &data, i, 0.0, 520.0)
+            );
+        }
+
+        // the density falls off from 501 towards 601 - 1200, so fewer than
+        // the uniform 80% of 501 - 600 sit above 520
+        assert_eq!(
+            uniform.fraction_within(schema, &data, 13, 520.0, 1200.0),
+            0.8
+        );
+        let above = linear.fraction_within(schema, &data, 13, 520.0, 1200.0);
+        assert!(above < 0.8);
+        let top = linear.fraction_within(schema, &data, 14, 600.0, 700.0);
+        assert!(top > uniform.fraction_within(schema, &data, 14, 600.0, 700.0));
+        assert!(linear.fraction_within(schema, &data, 14, 1100.0, 1200.0) >= 0.0);
+
+        // a NaN count, in the bucket or next to it, falls back to uniform
+        let mut nan = data;
+        nan[13] = f64::NAN;
+        assert_eq!(linear.fraction_within(schema, &nan, 13, 520.0, 1200.0), 0.8);
+        assert_eq!(
+            linear.fraction_within(schema, &nan, 12, 0.0, 475.0),
+            uniform.fraction_within(schema, &nan, 12, 0.0, 475.0)
+        );
+        assert!(linear
+            .fraction_within(schema, &data, 13, f64::NAN, 1200.0)
+            .is_finite());
+    }
+}
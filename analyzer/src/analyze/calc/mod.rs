@@ -1,5 +1,9 @@
+mod density;
 mod score;
 mod category;
+mod pathway;
 
+pub use density::ScoreDensity;
 pub use score::ScorePool;
 pub use category::CategoryPool;
+pub use pathway::PathwayPool;
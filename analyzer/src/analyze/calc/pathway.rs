@@ -0,0 +1,90 @@
+use crate::data::PathwayCode;
+use std::ops::{Add, Div, Index, IndexMut, Mul};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct PathwayPool([f64; PathwayCode::N]);
+
+impl PathwayPool {
+    const N: usize = PathwayCode::N;
+
+    pub fn zero() -> Self {
+        Self::default()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.0.iter().sum()
+    }
+
+    pub fn normalize(self) -> Self {
+        if self.total() == 0.0 {
+            Self::default()
+        } else {
+            self / self.total()
+        }
+    }
+}
+
+impl Default for PathwayPool {
+    fn default() -> Self {
+        Self([0_f64; Self::N])
+    }
+}
+
+impl From<[f64; PathwayCode::N]> for PathwayPool {
+    fn from(value: [f64; PathwayCode::N]) -> Self {
+        Self(value)
+    }
+}
+
+impl Index<PathwayCode> for PathwayPool {
+    type Output = f64;
+    fn index(&self, i: PathwayCode) -> &Self::Output {
+        &self.0[i as usize]
+    }
+}
+
+impl IndexMut<PathwayCode> for PathwayPool {
+    fn index_mut(&mut self, i: PathwayCode) -> &mut Self::Output {
+        &mut self.0[i as usize]
+    }
+}
+
+impl Index<usize> for PathwayPool {
+    type Output = f64;
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.0[i]
+    }
+}
+
+impl Add for PathwayPool {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self::Output {
+        for i in 0..Self::N {
+            self.0[i] += rhs.0[i];
+        }
+        self
+    }
+}
+
+impl Mul<f64> for PathwayPool {
+    type Output = Self;
+
+    fn mul(mut self, rhs: f64) -> Self::Output {
+        for i in 0..Self::N {
+            self.0[i] *= rhs;
+        }
+        self
+    }
+}
+
+impl Div<f64> for PathwayPool {
+    type Output = Self;
+
+    fn div(mut self, rhs: f64) -> Self::Output {
+        for i in 0..Self::N {
+            self.0[i] /= rhs;
+        }
+        self
+    }
+}
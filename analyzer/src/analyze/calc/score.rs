@@ -1,95 +1,154 @@
-use crate::data::{self, CategoryCode};
+use super::ScoreDensity;
+use crate::data::{self, BucketSchema, CategoryCode};
+use chrono::NaiveDate;
 use std::ops::{Add, Div, Index, IndexMut, Mul, Sub};
 
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-pub struct ScorePool([f64; data::Pool::N]);
+/// Candidates per bucket of `schema`. Pools combined by the arithmetic
+/// operators must share the schema, see `data::Pool::to_schema`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScorePool {
+    schema: &'static BucketSchema,
+    data: [f64; BucketSchema::MAX],
+    /// used by `within_score` and `invite` to split a bucket at a score
+    density: ScoreDensity,
+}
 
 impl ScorePool {
-    const N: usize = data::Pool::N;
+    const PNP_SCORE: i64 = 600;
+
+    pub fn zero(schema: &'static BucketSchema) -> Self {
+        Self {
+            schema,
+            data: [0_f64; BucketSchema::MAX],
+            density: ScoreDensity::default(),
+        }
+    }
+
+    pub fn new(schema: &'static BucketSchema, values: &[f64]) -> Self {
+        let mut pool = Self::zero(schema);
+        pool.data[..schema.len()].copy_from_slice(values);
+        pool
+    }
+
+    /// the latest consistent pool of `pool_data` published on or before `date`
+    pub fn latest_at(
+        pool_data: &[data::Pool],
+        date: NaiveDate,
+        density: ScoreDensity,
+    ) -> Option<Self> {
+        pool_data
+            .iter()
+            .rev()
+            .filter(|pool| pool.is_consistent())
+            .find(|pool| pool.date <= date)
+            .map(|pool| Self::from(*pool).with_density(density))
+    }
+
+    pub fn schema(&self) -> &'static BucketSchema {
+        self.schema
+    }
+
+    pub fn density(&self) -> ScoreDensity {
+        self.density
+    }
+
+    pub fn with_density(mut self, density: ScoreDensity) -> Self {
+        self.density = density;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.schema.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.schema.is_empty()
+    }
 
-    pub fn zero() -> Self {
-        Self::default()
+    pub fn values(&self) -> &[f64] {
+        &self.data[..self.len()]
     }
 
     pub fn total(&self) -> f64 {
-        self.0.iter().sum()
+        self.values().iter().sum()
     }
 
-    pub fn min_score(i: usize) -> i64 {
-        data::Pool::min_score(i)
+    pub fn min_score(&self, i: usize) -> i64 {
+        self.schema.min_score(i)
     }
 
-    pub fn max_score(i: usize) -> i64 {
-        data::Pool::max_score(i)
+    pub fn max_score(&self, i: usize) -> i64 {
+        self.schema.max_score(i)
+    }
+
+    // element-wise, over the buckets both sides share
+    fn zip_with(mut self, rhs: Self, f: impl Fn(f64, f64) -> f64) -> Self {
+        debug_assert_eq!(self.schema, rhs.schema);
+        for i in 0..self.len() {
+            self.data[i] = f(self.data[i], rhs.data[i]);
+        }
+        self
     }
 }
 
 impl Default for ScorePool {
     fn default() -> Self {
-        Self([0_f64; Self::N])
+        Self::zero(BucketSchema::latest())
     }
 }
 
 impl From<data::Pool> for ScorePool {
     fn from(value: data::Pool) -> Self {
-        Self(value.data)
+        Self {
+            schema: value.schema,
+            data: value.data,
+            density: ScoreDensity::default(),
+        }
     }
 }
 
 impl Index<usize> for ScorePool {
     type Output = f64;
     fn index(&self, i: usize) -> &Self::Output {
-        &self.0[i]
+        &self.data[i]
     }
 }
 
 impl IndexMut<usize> for ScorePool {
     fn index_mut(&mut self, i: usize) -> &mut Self::Output {
-        &mut self.0[i]
+        &mut self.data[i]
     }
 }
 
 impl Add for ScorePool {
     type Output = Self;
 
-    fn add(mut self, rhs: Self) -> Self::Output {
-        for i in 0..ScorePool::N {
-            self[i] = self[i] + rhs[i];
-        }
-        self
+    fn add(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |x, y| x + y)
     }
 }
 
 impl Sub for ScorePool {
     type Output = Self;
 
-    fn sub(mut self, rhs: Self) -> Self::Output {
-        for i in 0..ScorePool::N {
-            self[i] = self[i] - rhs[i];
-        }
-        self
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |x, y| x - y)
     }
 }
 
 impl Mul for ScorePool {
     type Output = Self;
 
-    fn mul(mut self, rhs: Self) -> Self::Output {
-        for i in 0..ScorePool::N {
-            self[i] = self[i] * rhs[i];
-        }
-        self
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |x, y| x * y)
     }
 }
 
 impl Div for ScorePool {
     type Output = Self;
 
-    fn div(mut self, rhs: Self) -> Self::Output {
-        for i in 0..ScorePool::N {
-            self[i] = self[i] / rhs[i];
-        }
-        self
+    fn div(self, rhs: Self) -> Self::Output {
+        self.zip_with(rhs, |x, y| x / y)
     }
 }
 
@@ -97,8 +156,8 @@ impl Mul<f64> for ScorePool {
     type Output = Self;
 
     fn mul(mut self, rhs: f64) -> Self::Output {
-        for i in 0..ScorePool::N {
-            self[i] = self[i] * rhs;
+        for i in 0..self.len() {
+            self[i] *= rhs;
         }
         self
     }
@@ -108,8 +167,8 @@ impl Div<f64> for ScorePool {
     type Output = Self;
 
     fn div(mut self, rhs: f64) -> Self::Output {
-        for i in 0..ScorePool::N {
-            self[i] = self[i] / rhs;
+        for i in 0..self.len() {
+            self[i] /= rhs;
         }
         self
     }
@@ -117,13 +176,17 @@ impl Div<f64> for ScorePool {
 
 impl ScorePool {
     fn multiplier_invite_general(&self, mut count: f64) -> Self {
-        let mut multiplier = Self::default();
+        let mut multiplier = Self::zero(self.schema);
 
-        for i in (0..Self::N).rev() {
+        for i in (0..self.len()).rev() {
+            if count <= 0.0 {
+                break;
+            }
             if count > self[i] {
                 multiplier[i] = 1.0;
                 count -= self[i]
             } else {
+                // `self[i]` is positive here, a drained bucket takes the branch above
                 multiplier[i] = count / self[i];
                 // count = 0.0;
                 break;
@@ -134,16 +197,12 @@ impl ScorePool {
     }
 
     fn multiplier_within_score(&self, min_score: f64, max_score: f64) -> Self {
-        let mut multiplier = Self::default();
-
-        for i in (0..Self::N).rev() {
-            let min_d = ScorePool::min_score(i) as f64;
-            let max_d = ScorePool::max_score(i) as f64;
-
-            let min_n = f64::min(max_d, f64::max(min_score, min_d));
-            let max_n = f64::max(min_d, f64::min(max_score, max_d));
+        let mut multiplier = Self::zero(self.schema);
 
-            multiplier[i] = (max_n - min_n) / (max_d - min_d);
+        for i in (0..self.len()).rev() {
+            multiplier[i] =
+                self.density
+                    .fraction_within(self.schema, self.values(), i, min_score, max_score);
         }
 
         multiplier
@@ -151,9 +210,12 @@ impl ScorePool {
 }
 
 impl ScorePool {
+    /// the buckets from the one holding the scores above `PNP_SCORE`
     pub fn pnp(self) -> Self {
-        let mut pool = Self::default();
-        pool[14] = self[14]; // 14-th bucket is 601 to 1200
+        let mut pool = Self::zero(self.schema);
+        for i in self.schema.bucket_of(Self::PNP_SCORE as f64 + 1.0)..self.len() {
+            pool[i] = self[i];
+        }
         pool
     }
 
@@ -166,89 +228,200 @@ impl ScorePool {
             self = self.non_pnp();
         }
 
-        if _invite.category.code == CategoryCode::General {
-            let m1 = self.multiplier_invite_general(_invite.size as f64);
+        if _invite.category.code == CategoryCode::GENERAL {
+            let m1 = self.multiplier_invite_general(_invite.size);
             self = self * m1;
         } else {
-            let m1 = self.multiplier_within_score(_invite.score as f64, 1200.0);
+            let m1 = self.multiplier_within_score(_invite.score, 1200.0);
             self = self * m1;
-            let m2 = _invite.size as f64 / self.total();
+            let total = self.total();
+            if total <= 0.0 {
+                // nobody left above the cutoff, e.g. drained by an earlier draw
+                return Self::zero(self.schema);
+            }
+            let m2 = _invite.size / total;
             self = self * m2;
         }
 
         self
     }
 
+    /// the pool with negative buckets, left over by rounding, set to zero
+    pub fn clamped(mut self) -> Self {
+        for i in 0..self.len() {
+            self[i] = f64::max(self[i], 0.0);
+        }
+        self
+    }
+
     pub fn within_score(self, min_score: f64, max_score: f64) -> Self {
         let multiplier = self.multiplier_within_score(min_score, max_score);
         self * multiplier
     }
+
+    /// The lowest score with at most `count` candidates above it, the
+    /// cutoff of a draw of `count` open to the whole pool. If `count` covers
+    /// the pool, the lowest score of its lowest non-empty bucket.
+    pub fn score_for_count(&self, count: f64) -> f64 {
+        let above = |score: f64| self.within_score(score, f64::INFINITY).total();
+        let lowest = (0..self.len()).find(|i| self[*i] > 0.0).unwrap_or(0);
+        let (mut lo, mut hi) = (
+            self.min_score(lowest) as f64,
+            self.max_score(self.len() - 1) as f64,
+        );
+        if above(lo) <= count {
+            return lo;
+        }
+        // `above` falls with the score, bisect to well under a point
+        while hi - lo > 0.01 {
+            let mid = (lo + hi) / 2.0;
+            if above(mid) > count {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        hi
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ScorePool;
+    use super::{ScoreDensity, ScorePool};
+    use crate::data::{test_raw_data, BucketSchema, CategoryCode, Invite};
 
     #[tokio::test]
     async fn non_pnp() {
-        let pool = ScorePool([
-            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
-        ]);
+        let pool = ScorePool::new(
+            &BucketSchema::CURRENT,
+            &[
+                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
+            ],
+        );
 
         let res0 = pool.non_pnp();
         assert_eq!(
-            res0.0,
+            res0.values(),
             [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 0.0]
         );
     }
 
     #[tokio::test]
     async fn multiplier_invite_general() {
-        let pool = ScorePool([
-            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
-        ]);
+        let pool = ScorePool::new(
+            &BucketSchema::CURRENT,
+            &[
+                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
+            ],
+        );
 
         let res0 = pool.multiplier_invite_general(69.0) * pool;
         assert_eq!(
-            res0.0,
+            res0.values(),
             [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 4.0, 11.0, 12.0, 13.0, 14.0, 15.0]
         );
 
         let res1 = pool.non_pnp().multiplier_invite_general(69.0) * pool.non_pnp();
         assert_eq!(
-            res1.0,
+            res1.values(),
             [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 0.0]
         );
     }
 
     #[tokio::test]
     async fn multiplier_within_score() {
-        let pool = ScorePool([
-            1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
-        ]);
+        let pool = ScorePool::new(
+            &BucketSchema::CURRENT,
+            &[
+                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
+            ],
+        );
 
         let res0 = pool.multiplier_within_score(0.0, 1200.0) * pool;
         assert_eq!(
-            res0.0,
+            res0.values(),
             [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0]
         );
 
         let res1 = pool.multiplier_within_score(466.0, 1200.0) * pool;
         assert_eq!(
-            res1.0,
+            res1.values(),
             [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 4.0, 11.0, 12.0, 13.0, 14.0, 15.0]
         );
 
         let res2 = pool.multiplier_within_score(0.0, 466.0) * pool;
         assert_eq!(
-            res2.0,
+            res2.values(),
             [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 6.0, 0.0, 0.0, 0.0, 0.0, 0.0]
         );
 
         let res3 = pool.multiplier_within_score(414.0, 466.0) * pool;
         assert_eq!(
-            res3.0,
+            res3.values(),
             [0.0, 0.0, 0.0, 0.0, 3.0, 6.0, 7.0, 8.0, 9.0, 6.0, 0.0, 0.0, 0.0, 0.0, 0.0]
         );
     }
+
+    #[tokio::test]
+    async fn within_score_density() {
+        let pool = ScorePool::new(
+            &BucketSchema::CURRENT,
+            &[
+                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 1400.0, 15.0,
+            ],
+        );
+        let linear = pool.with_density(ScoreDensity::Linear { steepness: 1.0 });
+
+        assert_eq!(pool.within_score(520.0, 1200.0)[13], 1120.0);
+        assert!(linear.within_score(520.0, 1200.0)[13] < 1120.0);
+        assert!((linear.within_score(0.0, 1200.0).total() - pool.total()).abs() < 1e-9);
+        assert_eq!((linear - pool).density(), linear.density());
+    }
+
+    #[tokio::test]
+    async fn score_for_count() {
+        let pool = ScorePool::new(
+            &BucketSchema::CURRENT,
+            &[
+                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0, 12.0, 13.0, 14.0, 15.0,
+            ],
+        );
+
+        assert!((pool.score_for_count(69.0) - 466.0).abs() < 0.01);
+        assert!((pool.score_for_count(15.0) - 600.0).abs() < 0.01);
+        assert_eq!(pool.score_for_count(pool.total()), 0.0);
+        assert_eq!(pool.pnp().score_for_count(20.0), 600.0);
+        let coarse = ScorePool::new(&BucketSchema::COARSE, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert_eq!(coarse.pnp().total(), 7.0);
+        let score = pool.score_for_count(100.0);
+        assert!((pool.within_score(score, f64::INFINITY).total() - 100.0).abs() < 0.1);
+    }
+
+    #[tokio::test]
+    async fn invite_drained() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let category = invite_data
+            .iter()
+            .find(|invite| invite.category.code != CategoryCode::GENERAL && invite.score > 500.0)
+            .unwrap();
+        let general = invite_data
+            .iter()
+            .find(|invite| invite.category.code == CategoryCode::GENERAL)
+            .unwrap();
+
+        // nobody above the cutoff, an earlier round took them all
+        let pool = ScorePool::new(
+            &BucketSchema::CURRENT,
+            &[
+                1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            ],
+        );
+        let res = pool.invite(category);
+        assert!(res.values().iter().all(|x| x.is_finite()));
+        assert_eq!(res.total(), 0.0);
+
+        let drained = ScorePool::zero(&BucketSchema::CURRENT);
+        assert_eq!(drained.invite(category).total(), 0.0);
+        assert_eq!(drained.invite(general).total(), 0.0);
+    }
 }
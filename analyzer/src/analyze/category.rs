@@ -1,42 +1,63 @@
 use super::calc::{CategoryPool, ScorePool};
-use crate::{data::{CategoryCode, Invite, Pool}, utils::console_log};
+use crate::data::{Category, CategoryCode, CategoryRound, Invite, Pool};
 use chrono::{Days, NaiveDate};
 use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
 pub struct CategoryAnalyzer;
 
 impl CategoryAnalyzer {
-    pub fn of_category_years<'a>(invite_data: &'a [Invite]) -> HashMap<i32, &'a [Invite]> {
+    pub fn of_category_years(invite_data: &[Invite]) -> HashMap<i32, &[Invite]> {
+        Self::of_category_periods(invite_data, |category| category.year())
+            .into_iter()
+            .map(|(year, invite_data)| (year.unwrap_or(0), invite_data))
+            .collect()
+    }
+
+    pub fn of_category_rounds(invite_data: &[Invite]) -> HashMap<CategoryRound, &[Invite]> {
+        Self::of_category_periods(invite_data, |category| category.round)
+            .into_iter()
+            .map(|(round, invite_data)| (round.unwrap_or_default(), invite_data))
+            .collect()
+    }
+
+    // a period runs from the first draw naming it to the first draw naming a
+    // later one, `None` holds all category invitations
+    fn of_category_periods<K: Copy + Ord + Hash>(
+        invite_data: &[Invite],
+        period_of: impl Fn(&Category) -> Option<K>,
+    ) -> HashMap<Option<K>, &[Invite]> {
         let mut map = HashMap::new();
 
-        let (invite_data, mut year) = 'ret: {
+        let (invite_data, mut period) = 'ret: {
             for i in 0..invite_data.len() {
-                if let Some(year) = invite_data[i].category.year {
-                    break 'ret (&invite_data[i..], year);
+                if let Some(period) = period_of(&invite_data[i].category) {
+                    break 'ret (&invite_data[i..], period);
                 }
             }
 
             // no category invitation available
-            map.insert(0, &invite_data[0..0]);
+            map.insert(None, &invite_data[0..0]);
             return map;
         };
 
         let mut i = 0;
         for j in 0..invite_data.len() {
-            let year_next = invite_data[j].category.year.unwrap_or(0);
-            if year < year_next {
-                map.insert(year, &invite_data[i..j]);
-                i = j;
-                year = year_next;
+            if let Some(period_next) = period_of(&invite_data[j].category) {
+                if period < period_next {
+                    map.insert(Some(period), &invite_data[i..j]);
+                    i = j;
+                    period = period_next;
+                }
             }
         }
 
         if !invite_data.is_empty() {
-            map.insert(year, &invite_data[i..invite_data.len()]);
+            map.insert(Some(period), &invite_data[i..invite_data.len()]);
         }
 
-        // insert all category invitation to 0
-        map.insert(0, invite_data);
+        // insert all category invitation to None
+        map.insert(None, invite_data);
 
         map
     }
@@ -45,7 +66,7 @@ impl CategoryAnalyzer {
         pool_data: &[Pool],
         invite_data: &[Invite],
     ) -> (Vec<NaiveDate>, Vec<CategoryPool>, HashSet<CategoryCode>) {
-        if pool_data.len() == 0 || invite_data.len() == 0 {
+        if pool_data.is_empty() || invite_data.is_empty() {
             return (Vec::new(), Vec::new(), HashSet::new());
         }
 
@@ -97,15 +118,15 @@ impl CategoryAnalyzer {
                     None
                 };
 
-                if invite.category.code == CategoryCode::General {
+                if invite.category.code == CategoryCode::GENERAL {
                     if let Some(invite_as_pool) = invite_as_pool {
-                        value[CategoryCode::Province] += invite_as_pool.pnp().total();
-                        value[CategoryCode::General] += invite_as_pool.non_pnp().total();
-                        categories.insert(CategoryCode::Province);
-                        categories.insert(CategoryCode::General);
+                        value[CategoryCode::PROVINCE] += invite_as_pool.pnp().total();
+                        value[CategoryCode::GENERAL] += invite_as_pool.non_pnp().total();
+                        categories.insert(CategoryCode::PROVINCE);
+                        categories.insert(CategoryCode::GENERAL);
                     } else {
-                        value[CategoryCode::General] += invite.size;
-                        categories.insert(CategoryCode::General);
+                        value[CategoryCode::GENERAL] += invite.size;
+                        categories.insert(CategoryCode::GENERAL);
                     }
                 } else {
                     value[invite.category.code] += invite.size;
@@ -129,7 +150,7 @@ impl CategoryAnalyzer {
         pool_data: &[Pool],
         invite_data: &[Invite],
     ) -> (Vec<NaiveDate>, Vec<CategoryPool>, HashSet<CategoryCode>) {
-        if pool_data.len() == 0 || invite_data.len() == 0 {
+        if pool_data.is_empty() || invite_data.is_empty() {
             return (Vec::new(), Vec::new(), HashSet::new());
         }
 
@@ -178,7 +199,7 @@ impl CategoryAnalyzer {
                     let invite_as_pool = pool.invite(invite);
                     pool_to_invite = Some(pool - invite_as_pool); // remove already invited candidates from the pool to avoid duplicate counts.
                     
-                    if invite.category.code != CategoryCode::General && invite.category.code != CategoryCode::Province {
+                    if invite.category.code != CategoryCode::GENERAL && invite.category.code != CategoryCode::PROVINCE {
                         value[invite.category.code] = invite.size / pool.within_score(invite.score, 600.0).total();
                         categories.insert(invite.category.code);
                     }
@@ -192,8 +213,6 @@ impl CategoryAnalyzer {
             i = i_next;
         }
 
-        console_log!("{:?}, {:?}, {:?}", labels, values, categories);
-
         (labels, values, categories)
     }
 }
@@ -201,20 +220,44 @@ impl CategoryAnalyzer {
 #[cfg(test)]
 mod tests {
     use super::CategoryAnalyzer;
-    use crate::data::wasm_invite_data;
-    use crate::data::InviteId;
+    use crate::data::{test_raw_data, CategoryRound, Invite, InviteId};
 
     #[tokio::test]
     async fn of_category_years() {
-        let invite_data = unsafe { wasm_invite_data().await.as_ref().unwrap() };
-        let map = CategoryAnalyzer::of_category_years(invite_data);
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let map = CategoryAnalyzer::of_category_years(&invite_data);
 
-        assert_eq!(map[&0].first().unwrap().id, InviteId::parse("252"));
+        assert_eq!(map[&0].first().unwrap().id, InviteId::parse("252").unwrap());
         assert_eq!(map[&0].last().unwrap().id, invite_data.last().unwrap().id);
 
-        assert_eq!(map[&2023].first().unwrap().id, InviteId::parse("252"));
-        assert_eq!(map[&2023].last().unwrap().id, InviteId::parse("286"));
+        assert_eq!(map[&2023].first().unwrap().id, InviteId::parse("252").unwrap());
+        assert_eq!(map[&2023].last().unwrap().id, InviteId::parse("286").unwrap());
 
-        assert_eq!(map[&2024].first().unwrap().id, InviteId::parse("287"));
+        assert_eq!(map[&2024].first().unwrap().id, InviteId::parse("287").unwrap());
+    }
+
+    #[tokio::test]
+    async fn of_category_rounds() {
+        let mut invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let round = |year, round| CategoryRound { year, round };
+
+        // IRCC redefines the category within the year
+        let k = invite_data
+            .iter()
+            .rposition(|invite| invite.category.round == Some(round(2024, 1)))
+            .unwrap();
+        invite_data[k].category.round = Some(round(2024, 2));
+        let map = CategoryAnalyzer::of_category_rounds(&invite_data);
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(map[&round(2023, 1)].first().unwrap().id, InviteId::parse("252").unwrap());
+        assert_eq!(map[&round(2023, 1)].last().unwrap().id, InviteId::parse("286").unwrap());
+        assert_eq!(map[&round(2024, 1)].first().unwrap().id, InviteId::parse("287").unwrap());
+        assert_eq!(map[&round(2024, 1)].last().unwrap().id, invite_data[k - 1].id);
+        assert_eq!(map[&round(2024, 2)].first().unwrap().id, invite_data[k].id);
+        assert_eq!(map[&CategoryRound::default()].first().unwrap().id, InviteId::parse("252").unwrap());
+
+        let years = CategoryAnalyzer::of_category_years(&invite_data);
+        assert_eq!(years[&2024].len(), map[&round(2024, 1)].len() + map[&round(2024, 2)].len());
     }
 }
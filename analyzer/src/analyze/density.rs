@@ -0,0 +1,113 @@
+use super::calc::{ScoreDensity, ScorePool};
+use crate::data::{CategoryCode, Invite, Pool};
+use chrono::NaiveDate;
+
+/// A general draw, which invites everyone above its cutoff, with the pool
+/// it was drawn from.
+#[derive(Debug, Clone, Copy)]
+pub struct DensitySample {
+    pub date: NaiveDate,
+    pub score: f64,
+    pub size: f64,
+    pub pool: ScorePool,
+}
+
+impl DensitySample {
+    /// candidates above the cutoff, as estimated with `density`
+    pub fn estimate(&self, density: ScoreDensity) -> f64 {
+        self.pool
+            .with_density(density)
+            .within_score(self.score, f64::INFINITY)
+            .total()
+    }
+}
+
+pub struct DensityAnalyzer;
+
+impl DensityAnalyzer {
+    const STEPS: usize = 20;
+
+    /// General draws, each with the latest consistent pool on or before its
+    /// date.
+    pub fn samples(pool_data: &[Pool], invite_data: &[Invite]) -> Vec<DensitySample> {
+        invite_data
+            .iter()
+            .filter(|invite| invite.category.code == CategoryCode::GENERAL)
+            .filter_map(|invite| {
+                let pool = pool_data
+                    .iter()
+                    .rev()
+                    .filter(|pool| pool.is_consistent())
+                    .find(|pool| pool.date <= invite.date)?;
+                Some(DensitySample {
+                    date: invite.date,
+                    score: invite.score,
+                    size: invite.size,
+                    pool: (*pool).into(),
+                })
+            })
+            .collect()
+    }
+
+    /// Root mean square of the relative errors of the estimates against the
+    /// draw sizes.
+    pub fn error(samples: &[DensitySample], density: ScoreDensity) -> f64 {
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let sum: f64 = samples
+            .iter()
+            .map(|sample| ((sample.estimate(density) - sample.size) / sample.size).powi(2))
+            .sum();
+        (sum / samples.len() as f64).sqrt()
+    }
+
+    /// The `Linear` density whose steepness best fits the draws of `samples`.
+    pub fn calibrate(samples: &[DensitySample]) -> ScoreDensity {
+        (0..=Self::STEPS)
+            .map(|k| ScoreDensity::Linear {
+                steepness: k as f64 / Self::STEPS as f64,
+            })
+            .min_by(|a, b| Self::error(samples, *a).total_cmp(&Self::error(samples, *b)))
+            .unwrap()
+    }
+
+    /// The density calibrated on the `samples` of `pool_data` and `invite_data`.
+    pub fn fit(pool_data: &[Pool], invite_data: &[Invite]) -> ScoreDensity {
+        Self::calibrate(&Self::samples(pool_data, invite_data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DensityAnalyzer;
+    use crate::analyze::calc::ScoreDensity;
+    use crate::data::{test_raw_data, CategoryCode, Invite, Pool};
+
+    #[tokio::test]
+    async fn calibrate() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let samples = DensityAnalyzer::samples(&pool_data, &invite_data);
+        let first = pool_data.iter().find(|pool| pool.is_consistent()).unwrap();
+
+        assert!(!samples.is_empty());
+        assert_eq!(
+            samples.len(),
+            invite_data
+                .iter()
+                .filter(|invite| invite.category.code == CategoryCode::GENERAL)
+                .filter(|invite| invite.date >= first.date)
+                .count()
+        );
+
+        let density = DensityAnalyzer::calibrate(&samples);
+        assert!(matches!(density, ScoreDensity::Linear { .. }));
+        assert!(
+            DensityAnalyzer::error(&samples, density)
+                <= DensityAnalyzer::error(&samples, ScoreDensity::Uniform) + 1e-12
+        );
+        assert_eq!(DensityAnalyzer::error(&[], density), 0.0);
+        assert_eq!(DensityAnalyzer::fit(&pool_data, &invite_data), density);
+    }
+}
@@ -0,0 +1,169 @@
+use super::calc::ScoreDensity;
+use super::rate::RateConfig;
+use super::simulate::{DrawSimulator, SimulatedDraw, SimulationConfig};
+use super::utils::serialize_date;
+use crate::data::{CategoryCode, Invite, Pool};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A value with the bounds of its confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Band {
+    pub low: f64,
+    pub mid: f64,
+    pub high: f64,
+}
+
+impl Band {
+    // `values` in ascending order, not empty
+    fn of(values: &[f64], confidence: f64) -> Self {
+        let quantile = |q: f64| values[(q * (values.len() - 1) as f64).round() as usize];
+        Self {
+            low: quantile((1.0 - confidence) / 2.0),
+            mid: quantile(0.5),
+            high: quantile((1.0 + confidence) / 2.0),
+        }
+    }
+}
+
+/// The next draw of one category.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CutoffForecast {
+    #[serde(skip)]
+    pub code: CategoryCode,
+    pub category: String,
+    pub label: String,
+    /// the median date of the next draw
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    pub score: Band,
+    pub size: Band,
+    /// share of the runs with a draw of the category
+    pub likelihood: f64,
+}
+
+pub struct ForecastAnalyzer;
+
+impl ForecastAnalyzer {
+    /// the share of simulated next draws within the bands
+    pub const CONFIDENCE: f64 = 0.8;
+
+    /// Forecast the next draw of each category from the first draw of each
+    /// run of `DrawSimulator`, in the order of the forecast dates.
+    pub fn next_draws(
+        pool_data: &[Pool],
+        invite_data: &[Invite],
+        density: ScoreDensity,
+        rate_config: &RateConfig,
+        config: &SimulationConfig,
+    ) -> Vec<CutoffForecast> {
+        let Some(simulator) = DrawSimulator::new(pool_data, invite_data, density, rate_config)
+        else {
+            return Vec::new();
+        };
+        let simulation = simulator.run(config);
+
+        // draws are in the order of the runs and of the days
+        let mut next: BTreeMap<CategoryCode, Vec<&SimulatedDraw>> = BTreeMap::new();
+        let mut seen = BTreeSet::new();
+        for draw in &simulation.draws {
+            if seen.insert((draw.run, draw.code)) {
+                next.entry(draw.code).or_default().push(draw);
+            }
+        }
+
+        let sorted = |mut values: Vec<f64>| {
+            values.sort_by(f64::total_cmp);
+            values
+        };
+        let mut forecasts: Vec<_> = next
+            .into_iter()
+            .map(|(code, draws)| {
+                let mut dates: Vec<_> = draws.iter().map(|draw| draw.date).collect();
+                dates.sort();
+                CutoffForecast {
+                    code,
+                    category: code.key(),
+                    label: code.as_str(),
+                    date: dates[dates.len() / 2],
+                    score: Band::of(
+                        &sorted(draws.iter().map(|draw| draw.score).collect()),
+                        Self::CONFIDENCE,
+                    ),
+                    size: Band::of(
+                        &sorted(draws.iter().map(|draw| draw.size).collect()),
+                        Self::CONFIDENCE,
+                    ),
+                    likelihood: draws.len() as f64 / config.runs as f64,
+                }
+            })
+            .collect();
+        forecasts.sort_by_key(|forecast| (forecast.date, forecast.code));
+        forecasts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ForecastAnalyzer;
+    use crate::analyze::calc::ScoreDensity;
+    use crate::analyze::rate::RateConfig;
+    use crate::analyze::simulate::SimulationConfig;
+    use crate::data::{test_raw_data, CategoryCode, Invite, Pool};
+
+    #[tokio::test]
+    async fn next_draws() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let rate_config = RateConfig::default();
+        let config = SimulationConfig {
+            runs: 50,
+            ..Default::default()
+        };
+        let forecasts = ForecastAnalyzer::next_draws(
+            &pool_data,
+            &invite_data,
+            ScoreDensity::Uniform,
+            &rate_config,
+            &config,
+        );
+
+        assert_eq!(
+            forecasts,
+            ForecastAnalyzer::next_draws(
+                &pool_data,
+                &invite_data,
+                ScoreDensity::Uniform,
+                &rate_config,
+                &config
+            )
+        );
+        let latest = invite_data.last().unwrap().date;
+        assert!(forecasts.windows(2).all(|x| x[0].date <= x[1].date));
+        for forecast in &forecasts {
+            assert!(forecast.date > latest);
+            assert!(forecast.score.low <= forecast.score.mid);
+            assert!(forecast.score.mid <= forecast.score.high);
+            assert!(forecast.size.low <= forecast.size.high);
+            assert!(forecast.likelihood > 0.0 && forecast.likelihood <= 1.0);
+        }
+
+        let general = forecasts
+            .iter()
+            .find(|forecast| forecast.code == CategoryCode::GENERAL)
+            .unwrap();
+        assert_eq!(general.category, "general");
+        assert!((500.0..=570.0).contains(&general.score.mid));
+
+        assert!(ForecastAnalyzer::next_draws(
+            &[],
+            &invite_data,
+            ScoreDensity::Uniform,
+            &rate_config,
+            &config
+        )
+        .is_empty());
+    }
+}
@@ -1,4 +1,35 @@
-pub(crate) mod calc;
-pub(crate) mod rate;
-pub(crate) mod category;
-pub(crate) mod smooth;
+pub mod backtest;
+pub mod calc;
+pub mod rate;
+pub mod rank;
+pub mod category;
+pub mod density;
+pub mod forecast;
+pub mod pathway;
+pub mod plan;
+pub mod probability;
+pub mod random;
+pub mod simulate;
+pub mod smooth;
+
+mod utils {
+    use chrono::NaiveDate;
+    use serde::Serializer;
+
+    pub fn serialize_date<S: Serializer>(
+        date: &NaiveDate,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&date.format("%Y-%m-%d"))
+    }
+
+    pub fn serialize_optional_date<S: Serializer>(
+        date: &Option<NaiveDate>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => serialize_date(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use super::calc::PathwayPool;
+use crate::data::{Invite, Pathway, PathwayCode};
+use crate::error::AnalyzerError;
+use chrono::{Datelike, NaiveDate};
+use std::collections::{BTreeMap, HashMap};
+
+/// How the invitations of a draw open to several pathways are split among them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathwaySplit {
+    /// evenly among the eligible pathways
+    Even,
+    /// proportionally to the weights of the eligible pathways, evenly if
+    /// none of them has a weight
+    Weighted(PathwayPool),
+}
+
+impl PathwaySplit {
+    pub fn split(&self, invite: &Invite) -> PathwayPool {
+        let codes = invite.pathway.codes();
+        let mut value = PathwayPool::zero();
+        if codes.is_empty() {
+            return value;
+        }
+
+        let weight = |code: PathwayCode| match self {
+            Self::Even => 1.0,
+            Self::Weighted(weights) => weights[code],
+        };
+        let total: f64 = codes.iter().map(|code| weight(*code)).sum();
+        for code in codes.iter().copied() {
+            value[code] = if total > 0.0 {
+                invite.size * weight(code) / total
+            } else {
+                invite.size / codes.len() as f64
+            };
+        }
+        value
+    }
+}
+
+impl std::fmt::Display for PathwaySplit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Even => write!(f, "even"),
+            Self::Weighted(weights) => {
+                let weights: Vec<_> = PathwayCode::values()
+                    .iter()
+                    .map(|code| weights[*code].to_string())
+                    .collect();
+                write!(f, "weighted:{}", weights.join(","))
+            }
+        }
+    }
+}
+
+/// `even` or `weighted:<PNP>,<CEC>,<FSW>,<FST>`, as displayed
+impl std::str::FromStr for PathwaySplit {
+    type Err = AnalyzerError;
+
+    fn from_str(x: &str) -> Result<Self, Self::Err> {
+        let parse = || match x.split_once(':') {
+            None => (x.trim() == "even").then_some(Self::Even),
+            Some((kind, weights)) if kind.trim() == "weighted" => {
+                let weights: Vec<f64> = weights
+                    .split(',')
+                    .map(|weight| weight.trim().parse().ok())
+                    .collect::<Option<_>>()?;
+                let weights: [f64; PathwayCode::N] = weights.try_into().ok()?;
+                weights
+                    .iter()
+                    .all(|weight| weight.is_finite() && *weight >= 0.0)
+                    .then_some(Self::Weighted(weights.into()))
+            }
+            Some(_) => None,
+        };
+        parse().ok_or(AnalyzerError::Format(format!(
+            "invalid pathway split {:?}",
+            x
+        )))
+    }
+}
+
+pub struct PathwayAnalyzer;
+
+impl PathwayAnalyzer {
+    /// Invitations by calendar year, 0 holds all invitations.
+    pub fn of_years(invite_data: &[Invite]) -> HashMap<i32, &[Invite]> {
+        let mut map = HashMap::new();
+        map.insert(0, invite_data);
+
+        let mut i = 0;
+        for j in 1..=invite_data.len() {
+            if j == invite_data.len() || invite_data[j].date.year() != invite_data[i].date.year() {
+                map.insert(invite_data[i].date.year(), &invite_data[i..j]);
+                i = j;
+            }
+        }
+
+        map
+    }
+
+    /// Cumulative invitations per pathway, one value per draw date.
+    pub fn invite_per_pathway(
+        invite_data: &[Invite],
+        split: PathwaySplit,
+    ) -> (Vec<NaiveDate>, Vec<PathwayPool>) {
+        let (mut labels, mut values): (Vec<NaiveDate>, Vec<PathwayPool>) = {
+            let capacity = invite_data.len();
+            (Vec::with_capacity(capacity), Vec::with_capacity(capacity))
+        };
+
+        let mut value = PathwayPool::zero();
+        for invite in invite_data {
+            value = value + split.split(invite);
+            if labels.last() == Some(&invite.date) {
+                *values.last_mut().unwrap() = value;
+            } else {
+                labels.push(invite.date);
+                values.push(value);
+            }
+        }
+
+        (labels, values)
+    }
+
+    /// Cumulative invitations per set of eligible pathways, e.g. CEC-only
+    /// draws against draws open to all programs, one value per draw date.
+    pub fn invite_per_draw_pathway(
+        invite_data: &[Invite],
+    ) -> (Vec<NaiveDate>, BTreeMap<Pathway, Vec<f64>>) {
+        let mut labels: Vec<NaiveDate> = Vec::with_capacity(invite_data.len());
+        for invite in invite_data {
+            if labels.last() != Some(&invite.date) {
+                labels.push(invite.date);
+            }
+        }
+
+        let mut map: BTreeMap<Pathway, Vec<f64>> = BTreeMap::new();
+        let mut i = 0;
+        for invite in invite_data {
+            while labels[i] != invite.date {
+                i += 1;
+            }
+            let values = map
+                .entry(invite.pathway)
+                .or_insert_with(|| vec![0.0; labels.len()]);
+            values[i..].iter_mut().for_each(|x| *x += invite.size);
+        }
+
+        (labels, map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathwayAnalyzer, PathwaySplit};
+    use crate::data::{test_raw_data, Invite, Pathway, PathwayCode};
+    use chrono::Datelike;
+
+    #[tokio::test]
+    async fn of_years() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let map = PathwayAnalyzer::of_years(&invite_data);
+
+        assert_eq!(map[&0].len(), invite_data.len());
+        assert!(map[&2023].iter().all(|invite| invite.date.year() == 2023));
+        let n: usize = map
+            .iter()
+            .filter(|(year, _)| **year != 0)
+            .map(|(_, x)| x.len())
+            .sum();
+        assert_eq!(n, invite_data.len());
+    }
+
+    #[tokio::test]
+    async fn invite_per_pathway() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let total: f64 = invite_data.iter().map(|invite| invite.size).sum();
+
+        let (labels, values) =
+            PathwayAnalyzer::invite_per_pathway(&invite_data, PathwaySplit::Even);
+        assert_eq!(labels.len(), values.len());
+        assert!(labels.windows(2).all(|x| x[0] < x[1]));
+        assert!((values.last().unwrap().total() - total).abs() < 1e-6);
+
+        // all the weight on CEC, draws open to CEC only count for it
+        let mut weights = [0.0; PathwayCode::N];
+        weights[PathwayCode::Cec as usize] = 1.0;
+        let (_, values) = PathwayAnalyzer::invite_per_pathway(
+            &invite_data,
+            PathwaySplit::Weighted(weights.into()),
+        );
+        let value = values.last().unwrap();
+        let only = |code: PathwayCode| -> f64 {
+            invite_data
+                .iter()
+                .filter(|invite| invite.pathway.codes() == [code])
+                .map(|invite| invite.size)
+                .sum()
+        };
+        let (pnp, fst) = (only(PathwayCode::Pnp), only(PathwayCode::Fst));
+        assert!((value[PathwayCode::Cec] - (total - pnp - fst)).abs() < 1e-6);
+        assert!((value[PathwayCode::Pnp] - pnp).abs() < 1e-6);
+        assert!((value[PathwayCode::Fst] - fst).abs() < 1e-6);
+        assert_eq!(value[PathwayCode::Fsw], 0.0);
+    }
+
+    #[tokio::test]
+    async fn invite_per_draw_pathway() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let (labels, map) = PathwayAnalyzer::invite_per_draw_pathway(&invite_data);
+
+        let cec = Pathway::parse("Canadian Experience Class");
+        let cec_only: f64 = invite_data
+            .iter()
+            .filter(|invite| invite.pathway == cec)
+            .map(|invite| invite.size)
+            .sum();
+        assert!(map.values().all(|values| values.len() == labels.len()));
+        assert_eq!(*map[&cec].last().unwrap(), cec_only);
+        assert_eq!(
+            map.values()
+                .map(|values| values.last().unwrap())
+                .sum::<f64>(),
+            invite_data.iter().map(|invite| invite.size).sum::<f64>()
+        );
+    }
+
+    #[test]
+    fn pathway_split() {
+        let mut weights = [0.0; PathwayCode::N];
+        weights[PathwayCode::Cec as usize] = 1.0;
+        let weighted = PathwaySplit::Weighted(weights.into());
+
+        assert_eq!("even".parse(), Ok(PathwaySplit::Even));
+        assert_eq!("weighted:0,1,0,0".parse(), Ok(weighted));
+        assert_eq!(weighted.to_string().parse(), Ok(weighted));
+        for invalid in [
+            "weighted:0,1,0",
+            "weighted:0,1,0,0,0",
+            "weighted:0,-1,0,0",
+            "weighted:0,inf,0,0",
+            "weighted",
+            "even:1",
+            "draw",
+        ] {
+            assert!(invalid.parse::<PathwaySplit>().is_err(), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use super::utils::{serialize_date, serialize_optional_date};
+use crate::data::{CategoryCode, Invite};
+use crate::error::AnalyzerError;
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The low and high end of the invitations planned for a year.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PlanTarget {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl PlanTarget {
+    pub fn map(self, f: impl Fn(f64) -> f64) -> Self {
+        Self {
+            min: f(self.min),
+            max: f(self.max),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PlanConfig {
+    targets: Vec<PlanTargetConfig>,
+    #[serde(default)]
+    pnp: Vec<PlanTargetConfig>,
+}
+
+#[derive(Deserialize)]
+struct PlanTargetConfig {
+    year: i32,
+    min: f64,
+    max: f64,
+}
+
+/// The draws a plan counts, provincial nominees have a plan of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanScope {
+    NonPnp,
+    Pnp,
+}
+
+impl PlanScope {
+    pub fn includes(&self, invite: &Invite) -> bool {
+        (invite.category.code == CategoryCode::PROVINCE) == (*self == Self::Pnp)
+    }
+}
+
+/// Plan and invitations of a year, or of a month of it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanProgress {
+    /// the middle of the period, for charts
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    pub year: i32,
+    pub month: Option<u32>,
+    pub plan_min: f64,
+    pub plan_max: f64,
+    /// invitations so far, for periods started by today
+    pub actual: Option<f64>,
+}
+
+/// What is left of the target of the current year, and what it takes to
+/// meet it. Pairs are for the low and high end of the target.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaPace {
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    pub target: PlanTarget,
+    /// invitations of the year up to `date`
+    pub invited: f64,
+    pub remaining: PlanTarget,
+    /// days of the year after `date`
+    pub days_left: i64,
+    pub per_week: PlanTarget,
+    pub per_month: PlanTarget,
+    /// draws of the year up to `date`
+    pub draws: usize,
+    /// draws in the rest of the year, at the cadence so far
+    pub draws_left: f64,
+    /// average size of the draws left to meet the target at that cadence
+    pub draw_size: Option<PlanTarget>,
+    /// draws left to meet the target at the average size so far
+    pub draws_needed: Option<PlanTarget>,
+    /// when the low end is met at the pace so far, or was met
+    #[serde(serialize_with = "serialize_optional_date")]
+    pub min_met_on: Option<NaiveDate>,
+    #[serde(serialize_with = "serialize_optional_date")]
+    pub max_met_on: Option<NaiveDate>,
+}
+
+/// The yearly targets of the IRCC levels plan, loaded from a config such as
+/// `plan.json`, and how the invitations keep up with them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanAnalyzer {
+    targets: BTreeMap<i32, PlanTarget>,
+    pnp: BTreeMap<i32, PlanTarget>,
+}
+
+impl Default for PlanAnalyzer {
+    fn default() -> Self {
+        Self::from_json(include_str!("../../plan.json")).unwrap()
+    }
+}
+
+impl PlanAnalyzer {
+    /// years after the latest draw shown with their targets
+    pub const YEARS_AHEAD: i32 = 2;
+
+    pub fn from_json(text: &str) -> Result<Self, AnalyzerError> {
+        fn format_err(e: impl std::fmt::Display) -> AnalyzerError {
+            AnalyzerError::Format(format!("plan config: {}", e))
+        }
+        fn targets(
+            config: Vec<PlanTargetConfig>,
+        ) -> Result<BTreeMap<i32, PlanTarget>, AnalyzerError> {
+            let mut targets = BTreeMap::new();
+            for PlanTargetConfig { year, min, max } in config {
+                if !(0.0 <= min && min <= max) {
+                    return Err(format_err(format!("invalid range of {}", year)));
+                }
+                if targets.insert(year, PlanTarget { min, max }).is_some() {
+                    return Err(format_err(format!("duplicate year {}", year)));
+                }
+            }
+            Ok(targets)
+        }
+
+        let config: PlanConfig = serde_json::from_str(text).map_err(format_err)?;
+        Ok(Self {
+            targets: targets(config.targets)?,
+            pnp: targets(config.pnp)?,
+        })
+    }
+
+    pub fn target(&self, scope: PlanScope, year: i32) -> Option<PlanTarget> {
+        match scope {
+            PlanScope::NonPnp => self.targets.get(&year).copied(),
+            PlanScope::Pnp => self.pnp.get(&year).copied(),
+        }
+    }
+
+    // the years of the draws of `scope`, and `YEARS_AHEAD` after, with a target
+    fn years(&self, invite_data: &[Invite], scope: PlanScope) -> Vec<(i32, PlanTarget)> {
+        let mut years = invite_data
+            .iter()
+            .filter(|invite| scope.includes(invite))
+            .map(|invite| invite.date.year());
+        let Some(first) = years.next() else {
+            return Vec::new();
+        };
+        let last = years.next_back().unwrap_or(first);
+        (first..=last + Self::YEARS_AHEAD)
+            .filter_map(|year| Some((year, self.target(scope, year)?)))
+            .collect()
+    }
+
+    fn invited(
+        invite_data: &[Invite],
+        scope: PlanScope,
+        within: impl Fn(NaiveDate) -> bool,
+    ) -> f64 {
+        invite_data
+            .iter()
+            .filter(|invite| scope.includes(invite) && within(invite.date))
+            .map(|invite| invite.size)
+            .sum()
+    }
+
+    /// The target and invitations of each year.
+    pub fn yearly(
+        &self,
+        invite_data: &[Invite],
+        scope: PlanScope,
+        today: NaiveDate,
+    ) -> Vec<PlanProgress> {
+        self.years(invite_data, scope)
+            .into_iter()
+            .map(|(year, target)| {
+                let start = NaiveDate::from_ymd_opt(year, 1, 1).unwrap();
+                PlanProgress {
+                    date: NaiveDate::from_ymd_opt(year, 7, 1).unwrap(),
+                    year,
+                    month: None,
+                    plan_min: target.min,
+                    plan_max: target.max,
+                    actual: (today >= start)
+                        .then(|| Self::invited(invite_data, scope, |date| date.year() == year)),
+                }
+            })
+            .collect()
+    }
+
+    /// The invitations of each month, against what is left of the target of
+    /// its year spread over the months left. Past months use up their
+    /// invitations, later months their share of the plan.
+    pub fn monthly(
+        &self,
+        invite_data: &[Invite],
+        scope: PlanScope,
+        today: NaiveDate,
+    ) -> Vec<PlanProgress> {
+        let mut progress = Vec::new();
+        for (year, target) in self.years(invite_data, scope) {
+            let (mut left_min, mut left_max) = (target.min, target.max);
+            for month in 1..=12 {
+                let months_left = (13 - month) as f64;
+                let plan_min = left_min / months_left;
+                let plan_max = left_max / months_left;
+
+                let start = NaiveDate::from_ymd_opt(year, month, 1).unwrap();
+                let actual = (today >= start).then(|| {
+                    Self::invited(invite_data, scope, |date| {
+                        date.year() == year && date.month() == month
+                    })
+                });
+                left_min = f64::max(0.0, left_min - actual.unwrap_or(plan_min));
+                left_max = f64::max(0.0, left_max - actual.unwrap_or(plan_max));
+
+                progress.push(PlanProgress {
+                    date: NaiveDate::from_ymd_opt(year, month, 15).unwrap(),
+                    year,
+                    month: Some(month),
+                    plan_min,
+                    plan_max,
+                    actual,
+                });
+            }
+        }
+        progress
+    }
+
+    /// The invitations of `year` added up, at each draw date.
+    pub fn cumulative(
+        invite_data: &[Invite],
+        scope: PlanScope,
+        year: i32,
+    ) -> Vec<(NaiveDate, f64)> {
+        let mut total = 0.0;
+        let mut points: Vec<(NaiveDate, f64)> = Vec::new();
+        for invite in invite_data
+            .iter()
+            .filter(|invite| scope.includes(invite) && invite.date.year() == year)
+        {
+            total += invite.size;
+            match points.last_mut() {
+                Some((date, value)) if *date == invite.date => *value = total,
+                _ => points.push((invite.date, total)),
+            }
+        }
+        points
+    }
+
+    /// `QuotaPace` of the year of `today`, if it has a target.
+    pub fn pace(
+        &self,
+        invite_data: &[Invite],
+        scope: PlanScope,
+        today: NaiveDate,
+    ) -> Option<QuotaPace> {
+        let year = today.year();
+        let target = self.target(scope, year)?;
+        let start = NaiveDate::from_ymd_opt(year, 1, 1)?;
+        let end = NaiveDate::from_ymd_opt(year, 12, 31)?;
+
+        let points: Vec<_> = Self::cumulative(invite_data, scope, year)
+            .into_iter()
+            .filter(|(date, _)| *date <= today)
+            .collect();
+        let invited = points.last().map_or(0.0, |(_, value)| *value);
+        let draws = invite_data
+            .iter()
+            .filter(|invite| scope.includes(invite))
+            .filter(|invite| start <= invite.date && invite.date <= today)
+            .count();
+
+        let days_elapsed = ((today - start).num_days() + 1) as f64;
+        let days_left = (end - today).num_days();
+        let days_per_month = ((end - start).num_days() + 1) as f64 / 12.0;
+        let remaining = target.map(|x| f64::max(0.0, x - invited));
+        // all of the remaining in the period of `days`, if less is left
+        let per = |days: f64| remaining.map(|x| x / f64::max(days_left as f64 / days, 1.0));
+        let draws_left = draws as f64 * days_left as f64 / days_elapsed;
+
+        let met_on = |x: f64| {
+            if invited >= x {
+                points
+                    .iter()
+                    .find(|(_, value)| *value >= x)
+                    .map(|(date, _)| *date)
+            } else if invited > 0.0 {
+                let days = ((x - invited) / (invited / days_elapsed)).ceil();
+                today.checked_add_days(Days::new(days as u64))
+            } else {
+                None
+            }
+        };
+
+        Some(QuotaPace {
+            date: today,
+            target,
+            invited,
+            remaining,
+            days_left,
+            per_week: per(7.0),
+            per_month: per(days_per_month),
+            draws,
+            draws_left,
+            draw_size: (draws_left > 0.0).then(|| remaining.map(|x| x / draws_left)),
+            draws_needed: (draws > 0).then(|| {
+                let size = invited / draws as f64;
+                remaining.map(|x| (x / size).ceil())
+            }),
+            min_met_on: met_on(target.min),
+            max_met_on: met_on(target.max),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PlanAnalyzer, PlanScope, PlanTarget};
+    use crate::data::{test_raw_data, CategoryCode, Invite};
+    use chrono::{Datelike, Days, NaiveDate};
+
+    #[test]
+    fn from_json() {
+        let plan = PlanAnalyzer::from_json(
+            r#"{"targets": [{"year": 2024, "min": 90000, "max": 116000}],
+                "pnp": [{"year": 2024, "min": 1000, "max": 2000}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            plan.target(PlanScope::NonPnp, 2024),
+            Some(PlanTarget {
+                min: 90000.0,
+                max: 116000.0
+            })
+        );
+        assert_eq!(plan.target(PlanScope::Pnp, 2024).unwrap().max, 2000.0);
+        assert_eq!(plan.target(PlanScope::NonPnp, 2023), None);
+
+        let invalid = |text| PlanAnalyzer::from_json(text).is_err();
+        assert!(invalid(
+            r#"{"targets": [{"year": 2024, "min": 2, "max": 1}]}"#
+        ));
+        assert!(invalid(
+            r#"{"targets": [{"year": 2024, "min": 1, "max": 2}, {"year": 2024, "min": 1, "max": 2}]}"#
+        ));
+        assert!(invalid("{}"));
+        assert_eq!(
+            PlanAnalyzer::default().target(PlanScope::NonPnp, 2023),
+            Some(PlanTarget {
+                min: 67750.0,
+                max: 88000.0
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn progress() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let plan = PlanAnalyzer::default();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 20).unwrap();
+        let last_year = invite_data.last().unwrap().date.year();
+
+        let yearly = plan.yearly(&invite_data, PlanScope::NonPnp, today);
+        assert_eq!(
+            yearly.last().unwrap().year,
+            last_year + PlanAnalyzer::YEARS_AHEAD
+        );
+        let year_2023 = yearly.iter().find(|x| x.year == 2023).unwrap();
+        let invited_2023: f64 = invite_data
+            .iter()
+            .filter(|invite| invite.date.year() == 2023)
+            .filter(|invite| invite.category.code != CategoryCode::PROVINCE)
+            .map(|invite| invite.size)
+            .sum();
+        assert_eq!(year_2023.actual, Some(invited_2023));
+        assert_eq!(yearly.last().unwrap().actual, None);
+
+        let monthly = plan.monthly(&invite_data, PlanScope::NonPnp, today);
+        assert_eq!(monthly.len(), yearly.len() * 12);
+        let months_2023: f64 = monthly
+            .iter()
+            .filter(|x| x.year == 2023)
+            .map(|x| x.actual.unwrap())
+            .sum();
+        assert_eq!(months_2023, invited_2023);
+
+        // a year without invitations yet spreads its target evenly
+        let future: Vec<_> = monthly.iter().filter(|x| x.year == 2025).collect();
+        assert!(future.iter().all(|x| x.actual.is_none()
+            && (x.plan_min - 96500.0 / 12.0).abs() < 1e-6
+            && (x.plan_max - 124000.0 / 12.0).abs() < 1e-6));
+        // the months after June 2024 share what is left after the invitations
+        let july = monthly
+            .iter()
+            .find(|x| x.year == 2024 && x.month == Some(7))
+            .unwrap();
+        let first_half: f64 = monthly
+            .iter()
+            .filter(|x| x.year == 2024)
+            .filter_map(|x| x.actual)
+            .sum();
+        assert!((july.plan_max - (116000.0 - first_half) / 6.0).abs() < 1e-6);
+
+        // no pnp targets are configured
+        assert!(plan.yearly(&invite_data, PlanScope::Pnp, today).is_empty());
+    }
+
+    #[tokio::test]
+    async fn pace() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let plan = PlanAnalyzer::default();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 20).unwrap();
+
+        let pace = plan.pace(&invite_data, PlanScope::NonPnp, today).unwrap();
+        let invited: f64 = invite_data
+            .iter()
+            .filter(|invite| invite.date.year() == 2024 && invite.date <= today)
+            .filter(|invite| invite.category.code != CategoryCode::PROVINCE)
+            .map(|invite| invite.size)
+            .sum();
+        assert_eq!(pace.invited, invited);
+        assert_eq!(pace.days_left, 194);
+        assert_eq!(pace.remaining.min, 90000.0 - invited);
+        assert!((pace.per_week.max - (116000.0 - invited) / (194.0 / 7.0)).abs() < 1e-6);
+        assert!(pace.per_month.min > pace.per_week.min);
+
+        // invitations so far at the cadence so far
+        let size = pace.draw_size.unwrap();
+        assert!((size.min * pace.draws_left - pace.remaining.min).abs() < 1e-6);
+        let needed = pace.draws_needed.unwrap();
+        assert!(needed.min <= needed.max);
+        // ahead of the pace of the target, both ends are met within the year
+        let per_day = invited / 172.0;
+        let days = ((90000.0 - invited) / per_day).ceil() as u64;
+        assert_eq!(pace.min_met_on, Some(today + Days::new(days)));
+        assert!(pace.min_met_on < pace.max_met_on);
+        assert!(pace.max_met_on.unwrap() <= NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
+
+        // 2023 ended above its low end, met on a draw date
+        let end = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        let pace = plan.pace(&invite_data, PlanScope::NonPnp, end).unwrap();
+        assert_eq!(pace.days_left, 0);
+        assert_eq!(pace.remaining.min, 0.0);
+        assert_eq!(pace.draw_size, None);
+        let met = pace.min_met_on.unwrap();
+        assert!(invite_data.iter().any(|invite| invite.date == met));
+
+        let cumulative = PlanAnalyzer::cumulative(&invite_data, PlanScope::NonPnp, 2023);
+        assert!(cumulative
+            .windows(2)
+            .all(|x| x[0].0 < x[1].0 && x[0].1 < x[1].1));
+        assert_eq!(cumulative.last().unwrap().1, pace.invited);
+
+        assert_eq!(
+            plan.pace(
+                &invite_data,
+                PlanScope::NonPnp,
+                NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use super::calc::{ScoreDensity, ScorePool};
+use super::rate::{RateAnalyzer, RateConfig};
+use super::utils::serialize_date;
+use crate::data::{CategoryCode, Invite, Pathway, Pool};
+use chrono::{Days, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// What decides which draws may invite a candidate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateProfile {
+    /// CRS score, including the 600 points of a provincial nomination
+    pub score: f64,
+    /// the programs the candidate is eligible for
+    pub pathway: Pathway,
+    /// the occupation categories the candidate is eligible for
+    pub categories: Vec<CategoryCode>,
+}
+
+impl CandidateProfile {
+    /// General and program draws only need a common pathway, occupation
+    /// draws also need the category.
+    pub fn is_eligible(&self, invite: &Invite) -> bool {
+        let code = invite.category.code;
+        invite.pathway.intersects(self.pathway)
+            && (code == CategoryCode::GENERAL
+                || code.group() == "program"
+                || self.categories.contains(&code))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HorizonProbability {
+    pub days: u32,
+    pub probability: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItaProbability {
+    pub score: f64,
+    /// date of the pool distribution the estimate starts from
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    /// estimated candidates ahead, now
+    pub ahead: f64,
+    /// probability of an ITA within each of `ProbabilityAnalyzer::HORIZONS`
+    pub horizons: Vec<HorizonProbability>,
+    /// probability of the first ITA on each day after `date`, up to the
+    /// longest horizon
+    pub waiting: Vec<f64>,
+    /// days until the ITA is more likely than not, if within the horizons
+    pub median_wait: Option<u32>,
+}
+
+// the recent draws of one category the candidate is eligible for
+#[derive(Debug, Clone, Default)]
+struct DrawType {
+    sizes: Vec<f64>,
+    shares: Vec<f64>,
+}
+
+impl DrawType {
+    // chance a draw of this type reaches `ahead` candidates of the whole pool
+    fn reach(&self, ahead: f64) -> f64 {
+        let share = self.shares.iter().sum::<f64>() / self.shares.len() as f64;
+        let n = self
+            .sizes
+            .iter()
+            .filter(|size| **size >= share * ahead)
+            .count();
+        n as f64 / self.sizes.len() as f64
+    }
+}
+
+pub struct ProbabilityAnalyzer;
+
+impl ProbabilityAnalyzer {
+    pub const HORIZONS: [u32; 4] = [30, 60, 90, 180];
+    /// draws before the latest pool used for cadence and sizes
+    pub const LOOKBACK_DAYS: u64 = 180;
+
+    /// Estimate the chance of an ITA for `profile`, from the latest
+    /// consistent pool.
+    ///
+    /// Each eligible category draws at its pace over the last
+    /// `LOOKBACK_DAYS`, with one of its recent sizes, and invites the
+    /// candidate if the size covers its share of the candidates ahead. Those
+    /// ahead grow with the projected pool increase rate of `rate_config`, see
+    /// `RateAnalyzer::projected_inflow`, and shrink with the recent
+    /// invitations above the score. `None` without pool data, or when the
+    /// inflow is not finite, as in `DrawSimulator::new`.
+    pub fn ita_probability(
+        pool_data: &[Pool],
+        invite_data: &[Invite],
+        profile: &CandidateProfile,
+        density: ScoreDensity,
+        rate_config: &RateConfig,
+    ) -> Option<ItaProbability> {
+        let pool = *pool_data.iter().rev().find(|pool| pool.is_consistent())?;
+        let date = pool.date;
+        let since = date - Days::new(Self::LOOKBACK_DAYS);
+        let pool_at = |date: NaiveDate| ScorePool::latest_at(pool_data, date, density);
+        let above = |pool: ScorePool, score: f64| pool.within_score(score, f64::INFINITY).total();
+
+        let recent: Vec<_> = invite_data
+            .iter()
+            .filter(|invite| invite.date > since && invite.date <= date)
+            .collect();
+
+        // invitations above the score per day
+        let drain = recent
+            .iter()
+            .filter_map(|invite| {
+                let pool = pool_at(invite.date)?;
+                Some(above(pool.invite(invite), profile.score))
+            })
+            .sum::<f64>()
+            / Self::LOOKBACK_DAYS as f64;
+
+        // new candidates above the score per day
+        let inflow = RateAnalyzer::projected_inflow(pool_data, invite_data, rate_config)
+            .with_density(density);
+        if !inflow.values().iter().all(|x| x.is_finite()) {
+            return None;
+        }
+        let inflow = above(inflow, profile.score);
+
+        let mut draw_types: BTreeMap<CategoryCode, DrawType> = BTreeMap::new();
+        for invite in recent.iter().filter(|invite| profile.is_eligible(invite)) {
+            let Some(pool) = pool_at(invite.date) else {
+                continue;
+            };
+            // the share of the pool above the cutoff this draw was open to
+            let reached = above(pool, invite.score);
+            let draw_type = draw_types.entry(invite.category.code).or_default();
+            draw_type.sizes.push(invite.size);
+            if reached > 0.0 {
+                draw_type.shares.push(f64::min(invite.size / reached, 1.0));
+            }
+        }
+        draw_types.retain(|_, draw_type| !draw_type.shares.is_empty());
+
+        let ahead = above(ScorePool::from(pool).with_density(density), profile.score);
+        let days = *Self::HORIZONS.iter().max().unwrap();
+        let mut survival = 1.0;
+        let mut waiting = Vec::with_capacity(days as usize);
+        for t in 1..=days {
+            let ahead_t = f64::max(0.0, ahead + (inflow - drain) * t as f64);
+            let miss: f64 = draw_types
+                .values()
+                .map(|draw_type| {
+                    let pace = f64::min(
+                        draw_type.sizes.len() as f64 / Self::LOOKBACK_DAYS as f64,
+                        1.0,
+                    );
+                    1.0 - pace * draw_type.reach(ahead_t)
+                })
+                .product();
+            waiting.push(survival * (1.0 - miss));
+            survival *= miss;
+        }
+
+        let within = |days: u32| waiting[..days as usize].iter().sum::<f64>();
+        Some(ItaProbability {
+            score: profile.score,
+            date,
+            ahead,
+            horizons: Self::HORIZONS
+                .iter()
+                .map(|days| HorizonProbability {
+                    days: *days,
+                    probability: within(*days),
+                })
+                .collect(),
+            median_wait: (1..=days).find(|days| within(*days) >= 0.5),
+            waiting,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CandidateProfile, ProbabilityAnalyzer};
+    use crate::analyze::calc::ScoreDensity;
+    use crate::analyze::rate::RateConfig;
+    use crate::data::{test_raw_data, CategoryCode, Invite, Pathway, Pool};
+    use chrono::Days;
+
+    #[tokio::test]
+    async fn ita_probability() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let with_invites =
+            |score: f64, pathway: &str, categories: &[&str], invite_data: &[Invite]| {
+                let profile = CandidateProfile {
+                    score,
+                    pathway: pathway.parse().unwrap(),
+                    categories: categories
+                        .iter()
+                        .map(|key| CategoryCode::from_key(key).unwrap())
+                        .collect(),
+                };
+                ProbabilityAnalyzer::ita_probability(
+                    &pool_data,
+                    invite_data,
+                    &profile,
+                    ScoreDensity::Uniform,
+                    &RateConfig::default(),
+                )
+            };
+        let probability = |score: f64, pathway: &str, categories: &[&str]| {
+            with_invites(score, pathway, categories, &invite_data).unwrap()
+        };
+        let within = |p: &super::ItaProbability, days: u32| {
+            p.horizons
+                .iter()
+                .find(|horizon| horizon.days == days)
+                .unwrap()
+                .probability
+        };
+
+        let pnp = probability(750.0, "PNP", &[]);
+        assert!(within(&pnp, 180) > 0.95);
+        assert!(pnp.median_wait.unwrap() <= 60);
+
+        let low = probability(300.0, "FSW", &[]);
+        assert_eq!(within(&low, 180), 0.0);
+        assert_eq!(low.median_wait, None);
+
+        let cec = probability(520.0, "CEC", &[]);
+        let french = probability(520.0, "CEC", &["french"]);
+        assert!(within(&french, 180) >= within(&cec, 180));
+        for p in [&pnp, &cec, &french] {
+            assert!(p
+                .horizons
+                .windows(2)
+                .all(|x| x[0].probability <= x[1].probability));
+            assert!((p.waiting.iter().sum::<f64>() - within(p, 180)).abs() < 1e-9);
+        }
+        assert!(within(&probability(480.0, "CEC", &[]), 90) <= within(&cec, 90));
+
+        // no estimate with a rate that cannot be told, here of a draw before
+        // the lookback
+        let mut nan = invite_data.clone();
+        let since = pool_data.last().unwrap().date - Days::new(ProbabilityAnalyzer::LOOKBACK_DAYS);
+        let i = nan.iter().rposition(|invite| invite.date <= since).unwrap();
+        nan[i].size = f64::NAN;
+        assert!(with_invites(520.0, "CEC", &[], &nan).is_none());
+        assert!(with_invites(300.0, "FSW", &[], &nan).is_none());
+
+        let profile = CandidateProfile {
+            score: 520.0,
+            pathway: Pathway::from_codes(&[]),
+            categories: Vec::new(),
+        };
+        assert!(invite_data
+            .iter()
+            .all(|invite| !profile.is_eligible(invite)));
+    }
+}
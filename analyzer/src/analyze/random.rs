@@ -0,0 +1,55 @@
+/// A small seeded generator (SplitMix64), so simulations are reproducible.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64
+    }
+
+    /// uniform in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_f64() * n as f64) as usize
+    }
+
+    pub fn pick<'a, T>(&mut self, values: &'a [T]) -> &'a T {
+        &values[self.below(values.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SeededRng;
+
+    #[test]
+    fn seeded() {
+        let values = |seed| {
+            let mut rng = SeededRng::new(seed);
+            (0..1000).map(|_| rng.next_f64()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(values(1), values(1));
+        assert_ne!(values(1), values(2));
+        assert!(values(1).iter().all(|x| (0.0..1.0).contains(x)));
+        let mean = values(3).iter().sum::<f64>() / 1000.0;
+        assert!((mean - 0.5).abs() < 0.05);
+
+        let mut rng = SeededRng::new(4);
+        assert!((0..1000).all(|_| rng.below(3) < 3));
+    }
+}
@@ -0,0 +1,106 @@
+use super::calc::{ScoreDensity, ScorePool};
+use super::utils::serialize_date;
+use crate::data::Pool;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Where a CRS score stands in a pool distribution.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoreRank {
+    pub score: f64,
+    /// date of the pool distribution used
+    #[serde(serialize_with = "serialize_date")]
+    pub date: NaiveDate,
+    /// estimated candidates with a higher score
+    pub ahead: f64,
+    /// share of the candidates with this score or lower, in percent
+    pub percentile: f64,
+    pub total: f64,
+    /// index of the bucket holding the score
+    pub bucket: usize,
+    pub bucket_label: String,
+}
+
+pub struct RankAnalyzer;
+
+impl RankAnalyzer {
+    /// Rank `score` in the latest consistent pool on or before `date`,
+    /// splitting its bucket with `density`. `None` if no pool is that old.
+    pub fn rank_for_score(
+        pool_data: &[Pool],
+        score: f64,
+        date: NaiveDate,
+        density: ScoreDensity,
+    ) -> Option<ScoreRank> {
+        let pool = pool_data
+            .iter()
+            .rev()
+            .filter(|pool| pool.is_consistent())
+            .find(|pool| pool.date <= date)?;
+        let total = pool.total();
+        let ahead = ScorePool::from(*pool)
+            .with_density(density)
+            .within_score(score, f64::INFINITY)
+            .total();
+        let bucket = pool.schema.bucket_of(score);
+
+        Some(ScoreRank {
+            score,
+            date: pool.date,
+            ahead,
+            percentile: if total == 0.0 {
+                0.0
+            } else {
+                (total - ahead) / total * 100.0
+            },
+            total,
+            bucket,
+            bucket_label: pool.schema.as_str(bucket),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RankAnalyzer;
+    use crate::analyze::calc::ScoreDensity;
+    use crate::data::{test_raw_data, Pool};
+    use chrono::Days;
+
+    #[tokio::test]
+    async fn rank_for_score() {
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let pool = pool_data.last().unwrap();
+        let rank = |score: f64| {
+            RankAnalyzer::rank_for_score(&pool_data, score, pool.date, ScoreDensity::Uniform)
+                .unwrap()
+        };
+
+        let r = rank(500.0);
+        assert_eq!(r.date, pool.date);
+        assert_eq!(r.bucket_label, "491 - 500");
+        assert_eq!(r.ahead, pool[13] + pool[14]);
+        assert!((r.percentile - (1.0 - r.ahead / pool.total()) * 100.0).abs() < 1e-9);
+
+        assert_eq!(rank(0.0).ahead, pool.total());
+        assert_eq!(rank(1200.0).ahead, 0.0);
+        assert_eq!(rank(1200.0).percentile, 100.0);
+        assert!(rank(520.0).ahead > rank(530.0).ahead);
+
+        // the pool as of the day before
+        let day = pool.date - Days::new(1);
+        let r = RankAnalyzer::rank_for_score(&pool_data, 500.0, day, ScoreDensity::Uniform);
+        assert!(r.unwrap().date < pool.date);
+        let first = pool_data.first().unwrap().date - Days::new(1);
+        assert!(
+            RankAnalyzer::rank_for_score(&pool_data, 500.0, first, ScoreDensity::Uniform).is_none()
+        );
+
+        // an inconsistent pool is skipped
+        let mut pool_data = pool_data.clone();
+        pool_data.last_mut().unwrap().consistent = false;
+        let r = RankAnalyzer::rank_for_score(&pool_data, 500.0, pool.date, ScoreDensity::Uniform);
+        assert!(r.unwrap().date < pool.date);
+    }
+}
@@ -3,7 +3,9 @@ use std::{cmp::Ordering, collections::BinaryHeap};
 use chrono::{Days, NaiveDate};
 
 use super::calc::ScorePool;
-use crate::data::{Invite, Pool};
+use super::smooth::Smoothing;
+use crate::data::{BucketSchema, Invite, Pool};
+use crate::error::AnalyzerError;
 
 #[derive(Debug, Clone, Copy)]
 struct RateModifier {
@@ -33,10 +35,10 @@ struct RateAccumulator {
 }
 
 impl RateAccumulator {
-    fn new() -> Self {
+    fn new(schema: &'static BucketSchema) -> Self {
         Self {
             _heap: BinaryHeap::new(),
-            _rate: ScorePool::zero(),
+            _rate: ScorePool::zero(schema),
         }
     }
 
@@ -65,19 +67,196 @@ impl RateAccumulator {
     }
 }
 
+/// When the invited candidates who accept leave the pool, after their draw.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemovalKernel {
+    /// evenly over `days`
+    Uniform { days: u32 },
+    /// decaying with a mean of `mean_days`, cut off after `days`
+    Exponential { mean_days: f64, days: u32 },
+    /// `shares` of the candidates leave within each `bin_days` in turn
+    Histogram { bin_days: u32, shares: Vec<f64> },
+}
+
+impl RemovalKernel {
+    // bins of the exponential kernel, a week each
+    const EXPONENTIAL_BIN_DAYS: u32 = 7;
+
+    /// whether some candidates leave, within a positive number of days
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Uniform { days } => *days > 0,
+            Self::Exponential { mean_days, days } => {
+                mean_days.is_finite() && *mean_days > 0.0 && *days > 0
+            }
+            Self::Histogram { bin_days, shares } => {
+                *bin_days > 0
+                    && shares
+                        .iter()
+                        .all(|share| share.is_finite() && *share >= 0.0)
+                    && shares.iter().sum::<f64>() > 0.0
+            }
+        }
+    }
+
+    /// days until the last candidate leaves
+    pub fn days(&self) -> u32 {
+        self.bins().iter().map(|(days, _)| days).sum()
+    }
+
+    /// The days of consecutive bins and the share of the candidates leaving
+    /// evenly within each, the shares add up to 1.
+    pub fn bins(&self) -> Vec<(u32, f64)> {
+        match self {
+            Self::Uniform { days } => vec![(*days, 1.0)],
+            Self::Exponential { mean_days, days } => {
+                let left = |day: u32| (-(day as f64) / mean_days).exp();
+                let total = 1.0 - left(*days);
+                (0..*days)
+                    .step_by(Self::EXPONENTIAL_BIN_DAYS as usize)
+                    .map(|start| {
+                        let end = u32::min(start + Self::EXPONENTIAL_BIN_DAYS, *days);
+                        (end - start, (left(start) - left(end)) / total)
+                    })
+                    .collect()
+            }
+            Self::Histogram { bin_days, shares } => {
+                let total: f64 = shares.iter().sum();
+                shares
+                    .iter()
+                    .map(|share| (*bin_days, share / total))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for RemovalKernel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Uniform { days } => write!(f, "uniform:{}", days),
+            Self::Exponential { mean_days, days } => {
+                write!(f, "exponential:{}:{}", mean_days, days)
+            }
+            Self::Histogram { bin_days, shares } => {
+                let shares: Vec<_> = shares.iter().map(f64::to_string).collect();
+                write!(f, "histogram:{}:{}", bin_days, shares.join(","))
+            }
+        }
+    }
+}
+
+/// `uniform:<days>`, `exponential:<mean days>:<days>` or
+/// `histogram:<bin days>:<share>,<share>,...`, as displayed
+impl std::str::FromStr for RemovalKernel {
+    type Err = AnalyzerError;
+
+    fn from_str(x: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let mut parts = x.split(':').map(str::trim);
+            let kernel = match parts.next()? {
+                "uniform" => Self::Uniform {
+                    days: parts.next()?.parse().ok()?,
+                },
+                "exponential" => Self::Exponential {
+                    mean_days: parts.next()?.parse().ok()?,
+                    days: parts.next()?.parse().ok()?,
+                },
+                "histogram" => Self::Histogram {
+                    bin_days: parts.next()?.parse().ok()?,
+                    shares: parts
+                        .next()?
+                        .split(',')
+                        .map(|share| share.trim().parse().ok())
+                        .collect::<Option<_>>()?,
+                },
+                _ => return None,
+            };
+            (kernel.is_valid() && parts.next().is_none()).then_some(kernel)
+        };
+        parse().ok_or(AnalyzerError::Format(format!(
+            "invalid removal kernel {:?}",
+            x
+        )))
+    }
+}
+
+/// Parameters of `RateAnalyzer` and of the rate chart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateConfig {
+    /// when invited candidates submit their application and leave the pool.
+    /// The rates of its first days are not reported as they miss the
+    /// invitations before the first pool.
+    pub removal: RemovalKernel,
+    /// share of the invited candidates who accept, the others stay in the
+    /// pool
+    pub acceptance: f64,
+    /// latest rates averaged by `RateAnalyzer::projected_rate`, one per
+    /// interval between the dates a pool, a draw or a submission window
+    /// changes the rate
+    pub projection_window: usize,
+    /// smoother of the rate chart
+    pub smoothing: Smoothing,
+    /// days after the latest rate the projected rate is drawn at
+    pub horizon_days: u32,
+}
+
+impl Default for RateConfig {
+    fn default() -> Self {
+        Self {
+            removal: RemovalKernel::Uniform { days: 15 },
+            acceptance: 1.0,
+            projection_window: 181,
+            // a 60 days window
+            smoothing: Smoothing::Exponential {
+                alpha: 2.0 / (60.0 + 1.0),
+            },
+            horizon_days: 120,
+        }
+    }
+}
+
+impl RateConfig {
+    /// An error for a removal kernel which divides by zero days or shares,
+    /// or for an acceptance outside 0 to 1.
+    pub fn validate(&self) -> Result<(), AnalyzerError> {
+        if !self.removal.is_valid() {
+            return Err(AnalyzerError::Format(format!(
+                "invalid removal kernel {:?}",
+                self.removal.to_string()
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.acceptance) {
+            return Err(AnalyzerError::Format(format!(
+                "invalid acceptance {}",
+                self.acceptance
+            )));
+        }
+        Ok(())
+    }
+}
+
 pub struct RateAnalyzer;
 
 impl RateAnalyzer {
-    pub const SUBMIT_DAYS: usize = 15;
-
+    /// `pool_data` must share one schema, as returned by `Pool::parse_all`.
+    /// No rates for a `config` failing `RateConfig::validate`.
     pub fn pool_increase_rate(
         pool_data: &[Pool],
         invite_data: &[Invite],
+        config: &RateConfig,
     ) -> (Vec<NaiveDate>, Vec<ScorePool>) {
-        if pool_data.len() == 0 {
+        if pool_data.is_empty() || config.validate().is_err() {
             return (Vec::new(), Vec::new());
         }
 
+        let removal = config.removal.bins();
+        let removal_days = config.removal.days() as u64;
+        let removal_per_day: Vec<_> = removal
+            .iter()
+            .map(|(days, share)| config.acceptance * share / *days as f64)
+            .collect();
+
         let i_0 = pool_data.first().unwrap().date;
         let i_n = pool_data.last().unwrap().date + Days::new(1);
 
@@ -94,8 +273,9 @@ impl RateAnalyzer {
             .filter(|invite| invite.date >= i_0)
             .collect();
 
-        let mut rate_acc = RateAccumulator::new();
-        let mut pool_to_invite = ScorePool::zero(); // based on how date_0 is defined, this value will be immediately re-assigned.
+        let schema = Pool::schema_of(pool_data);
+        let mut rate_acc = RateAccumulator::new(schema);
+        let mut pool_to_invite = ScorePool::zero(schema); // based on how date_0 is defined, this value will be immediately re-assigned.
 
         // there are two potential increase from the raw data
         // 1. pool-based increase: the number difference directly computed by
@@ -151,11 +331,20 @@ impl RateAnalyzer {
                 }
 
                 let invite_as_pool = pool_to_invite.invite(invite);
-                rate_acc.insert(RateModifier {
-                    value: invite_as_pool / Self::SUBMIT_DAYS as f64,
-                    expiry: i + Days::new(Self::SUBMIT_DAYS as u64),
-                });
-                pool_to_invite = pool_to_invite - invite_as_pool; // remove already invited candidates from the pool to avoid duplicate counts.
+                // the modifiers still running in a bin add up to its rate
+                let mut end = 0;
+                for (k, (days, _)) in removal.iter().enumerate() {
+                    end += *days as u64;
+                    let value =
+                        removal_per_day[k] - removal_per_day.get(k + 1).copied().unwrap_or(0.0);
+                    if value != 0.0 {
+                        rate_acc.insert(RateModifier {
+                            value: invite_as_pool * value,
+                            expiry: i + Days::new(end),
+                        });
+                    }
+                }
+                pool_to_invite = (pool_to_invite - invite_as_pool).clamped(); // remove already invited candidates from the pool to avoid duplicate counts.
 
                 invites.pop();
             }
@@ -168,8 +357,8 @@ impl RateAnalyzer {
                 }
             }
 
-            if i > i_0 + Days::new(Self::SUBMIT_DAYS as u64) {
-                // ignore first 60 days since they are under estimated.
+            if i > i_0 + Days::new(removal_days) {
+                // ignore the first submission window since it is under estimated.
 
                 let interval = (i_next - i).num_days();
                 labels.push(i + Days::new(((interval + 1) / 2) as u64)); // use the mid-point
@@ -182,15 +371,185 @@ impl RateAnalyzer {
         (labels, rates)
     }
 
-    pub fn projected_rate(rate_data: &Vec<ScorePool>) -> ScorePool {
-        const PAST_DAYS: usize = 181;
+    pub fn projected_rate(rate_data: &[ScorePool], config: &RateConfig) -> ScorePool {
+        let window = config.projection_window.max(1);
         rate_data
             .iter()
             .copied()
             .rev()
-            .take(PAST_DAYS)
+            .take(window)
             .reduce(|x, y| x + y)
-            .unwrap_or(ScorePool::zero())
-            / PAST_DAYS as f64
+            .unwrap_or_default()
+            / window as f64
+    }
+
+    /// `projected_rate` of the consistent pools of `pool_data`, the daily
+    /// inflow the projections grow the latest pool with.
+    pub fn projected_inflow(
+        pool_data: &[Pool],
+        invite_data: &[Invite],
+        config: &RateConfig,
+    ) -> ScorePool {
+        let pool_data: Vec<_> = pool_data
+            .iter()
+            .filter(|pool| pool.is_consistent())
+            .copied()
+            .collect();
+        let (_, rate_data) = Self::pool_increase_rate(&pool_data, invite_data, config);
+        Self::projected_rate(&rate_data, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateAnalyzer, RateConfig, RemovalKernel};
+    use crate::data::{test_raw_data, Invite, Pool};
+    use chrono::Days;
+
+    #[test]
+    fn removal_kernel() {
+        let total = |kernel: &RemovalKernel| kernel.bins().iter().map(|(_, share)| share).sum();
+        let near = |x: f64, y: f64| (x - y).abs() < 1e-9;
+
+        let uniform: RemovalKernel = "uniform:15".parse().unwrap();
+        assert_eq!(uniform, RemovalKernel::Uniform { days: 15 });
+        assert_eq!(uniform.bins(), vec![(15, 1.0)]);
+
+        let exponential: RemovalKernel = "exponential:20:60".parse().unwrap();
+        let bins = exponential.bins();
+        assert_eq!(exponential.days(), 60);
+        assert_eq!(bins.len(), 9);
+        assert_eq!(bins.last().unwrap().0, 4);
+        assert!(near(total(&exponential), 1.0));
+        assert!(bins.windows(2).all(|x| x[0].1 > x[1].1));
+
+        let histogram: RemovalKernel = "histogram:10:2,1,1".parse().unwrap();
+        assert_eq!(
+            histogram.to_string().parse::<RemovalKernel>(),
+            Ok(histogram.clone())
+        );
+        assert_eq!(exponential.to_string(), "exponential:20:60");
+        assert_eq!(histogram.days(), 30);
+        assert_eq!(histogram.bins(), vec![(10, 0.5), (10, 0.25), (10, 0.25)]);
+
+        for invalid in [
+            "uniform:0",
+            "uniform",
+            "exponential:0:60",
+            "exponential:20",
+            "histogram:7:0,0",
+            "histogram:7:1,-1",
+            "triangle:7",
+            "uniform:15:1",
+        ] {
+            assert!(invalid.parse::<RemovalKernel>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[tokio::test]
+    async fn finite() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data: Vec<_> = Pool::parse_all(test_raw_data().await)
+            .unwrap()
+            .into_iter()
+            .filter(|pool| pool.is_consistent())
+            .collect();
+        let config = RateConfig::default();
+
+        // some buckets are drained by the category rounds between two
+        // distributions, which must not turn the rate into NaN
+        let (_, rate_data) = RateAnalyzer::pool_increase_rate(&pool_data, &invite_data, &config);
+        assert!(!rate_data.is_empty());
+        for rate in &rate_data {
+            assert!(rate.values().iter().all(|x| x.is_finite()));
+        }
+        let projected = RateAnalyzer::projected_rate(&rate_data, &config);
+        assert!(projected.values().iter().all(|x| x.is_finite()));
+        assert!(projected.total() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn config() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data: Vec<_> = Pool::parse_all(test_raw_data().await)
+            .unwrap()
+            .into_iter()
+            .filter(|pool| pool.is_consistent())
+            .collect();
+        let first = pool_data[0].date;
+        let rates = |config: &RateConfig| {
+            RateAnalyzer::pool_increase_rate(&pool_data, &invite_data, config)
+        };
+
+        let config = RateConfig::default();
+        let (labels, rate_data) = rates(&config);
+        assert_eq!(labels.len(), rate_data.len());
+        assert!(labels[0] > first + Days::new(config.removal.days() as u64));
+        let projected = RateAnalyzer::projected_rate(&rate_data, &config);
+
+        // a histogram of one bin is the uniform kernel
+        let histogram = RateConfig {
+            removal: RemovalKernel::Histogram {
+                bin_days: 15,
+                shares: vec![1.0],
+            },
+            ..config.clone()
+        };
+        assert_eq!(rates(&histogram).0, labels);
+
+        let slow = RateConfig {
+            removal: "exponential:20:60".parse().unwrap(),
+            ..config.clone()
+        };
+        let (slow_labels, slow_rates) = rates(&slow);
+        assert!(slow_labels[0] > first + Days::new(60));
+        assert_ne!(RateAnalyzer::projected_rate(&slow_rates, &slow), projected);
+
+        // the invitees who decline stay in the pool, and do not make room
+        // for new candidates
+        let declined = RateConfig {
+            acceptance: 0.8,
+            ..config.clone()
+        };
+        let (_, declined_rates) = rates(&declined);
+        let declined = RateAnalyzer::projected_rate(&declined_rates, &declined);
+        assert!(declined.total() < projected.total());
+
+        // kernels dividing by zero and shares beyond 0 to 1 give no rates
+        assert!(config.validate().is_ok());
+        for invalid in [
+            RateConfig {
+                removal: RemovalKernel::Uniform { days: 0 },
+                ..config.clone()
+            },
+            RateConfig {
+                removal: RemovalKernel::Histogram {
+                    bin_days: 15,
+                    shares: vec![0.0, 0.0],
+                },
+                ..config.clone()
+            },
+            RateConfig {
+                acceptance: 1.5,
+                ..config.clone()
+            },
+            RateConfig {
+                acceptance: f64::NAN,
+                ..config.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+            assert!(rates(&invalid).1.is_empty());
+        }
+
+        // the latest rate alone
+        let latest = RateConfig {
+            projection_window: 1,
+            ..config
+        };
+        assert_eq!(
+            RateAnalyzer::projected_rate(&rate_data, &latest),
+            *rate_data.last().unwrap()
+        );
     }
 }
@@ -0,0 +1,359 @@
+use super::calc::{ScoreDensity, ScorePool};
+use super::random::SeededRng;
+use super::rate::{RateAnalyzer, RateConfig};
+use crate::data::{CategoryCode, Invite, Pathway, PathwayCode, Pool};
+use chrono::{Days, NaiveDate};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulationConfig {
+    /// independent runs, each a possible future
+    pub runs: usize,
+    /// days simulated after the latest pool
+    pub days: u32,
+    pub seed: u64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            runs: 200,
+            days: 180,
+            seed: 0,
+        }
+    }
+}
+
+/// A draw of one run of a `Simulation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulatedDraw {
+    pub run: usize,
+    pub date: NaiveDate,
+    pub code: CategoryCode,
+    pub size: f64,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    /// date the runs start from, see `DrawSimulator::new`
+    pub date: NaiveDate,
+    pub runs: usize,
+    pub draws: Vec<SimulatedDraw>,
+}
+
+impl Simulation {
+    /// the draw types drawn in any run
+    pub fn codes(&self) -> Vec<CategoryCode> {
+        let mut codes: Vec<_> = self.draws.iter().map(|draw| draw.code).collect();
+        codes.sort();
+        codes.dedup();
+        codes
+    }
+
+    pub fn cutoffs(&self, code: CategoryCode) -> CutoffDistribution {
+        let mut scores: Vec<_> = self
+            .draws
+            .iter()
+            .filter(|draw| draw.code == code)
+            .map(|draw| draw.score)
+            .collect();
+        scores.sort_by(f64::total_cmp);
+        CutoffDistribution {
+            code,
+            runs: self.runs,
+            scores,
+        }
+    }
+}
+
+/// The cutoffs of all simulated draws of one type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CutoffDistribution {
+    pub code: CategoryCode,
+    pub runs: usize,
+    /// in ascending order
+    pub scores: Vec<f64>,
+}
+
+impl CutoffDistribution {
+    /// the cutoff below which a share `q` of the draws fall
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.scores.is_empty() {
+            return None;
+        }
+        let k = (q.clamp(0.0, 1.0) * (self.scores.len() - 1) as f64).round() as usize;
+        Some(self.scores[k])
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.scores.is_empty() {
+            return None;
+        }
+        Some(self.scores.iter().sum::<f64>() / self.scores.len() as f64)
+    }
+
+    pub fn draws_per_run(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        self.scores.len() as f64 / self.runs as f64
+    }
+}
+
+// the recent draws of one category, resampled by the runs
+#[derive(Debug, Clone, Default)]
+struct DrawType {
+    invites: Vec<Invite>,
+    /// share of the pool above the cutoff each invite was open to
+    shares: Vec<f64>,
+    /// days between consecutive draws
+    gaps: Vec<u64>,
+}
+
+impl DrawType {
+    fn gap(&self, rng: &mut SeededRng) -> Days {
+        Days::new(u64::max(*rng.pick(&self.gaps), 1))
+    }
+}
+
+/// Evolves the latest pool day by day with the projected increase rate, and
+/// removes the invitations of draws resampled from the recent ones.
+#[derive(Debug, Clone)]
+pub struct DrawSimulator {
+    date: NaiveDate,
+    pool: ScorePool,
+    inflow: ScorePool,
+    draw_types: BTreeMap<CategoryCode, DrawType>,
+}
+
+impl DrawSimulator {
+    /// draws before the latest pool used for cadence, sizes and shares
+    pub const LOOKBACK_DAYS: u64 = 365;
+
+    /// Fits the draw types with at least two draws in the last
+    /// `LOOKBACK_DAYS`: the gaps between their draws, and the size and share
+    /// of the pool each draw was open to.
+    ///
+    /// The runs start from the latest consistent pool, caught up with the
+    /// draws published since, and grow with the inflow of `rate_config`, see
+    /// `RateAnalyzer::projected_inflow`. `None` without pool data, or when
+    /// the inflow is not finite, which the daily pools would hide.
+    pub fn new(
+        pool_data: &[Pool],
+        invite_data: &[Invite],
+        density: ScoreDensity,
+        rate_config: &RateConfig,
+    ) -> Option<Self> {
+        let latest = *pool_data.iter().rev().find(|pool| pool.is_consistent())?;
+        let date = invite_data
+            .iter()
+            .map(|invite| invite.date)
+            .fold(latest.date, NaiveDate::max);
+        let since = date - Days::new(Self::LOOKBACK_DAYS);
+        let pool_at = |date: NaiveDate| ScorePool::latest_at(pool_data, date, density);
+        let inflow = RateAnalyzer::projected_inflow(pool_data, invite_data, rate_config)
+            .with_density(density);
+        if !inflow.values().iter().all(|x| x.is_finite()) {
+            return None;
+        }
+
+        let mut draw_types: BTreeMap<CategoryCode, DrawType> = BTreeMap::new();
+        let mut last: BTreeMap<CategoryCode, NaiveDate> = BTreeMap::new();
+        for invite in invite_data.iter().filter(|invite| invite.date > since) {
+            let Some(pool) = pool_at(invite.date) else {
+                continue;
+            };
+            let code = invite.category.code;
+            // even general draws reach fewer than estimated above their
+            // cutoff, e.g. for profiles expiring before the draw
+            let reached = Self::open_to(pool, invite)
+                .within_score(invite.score, f64::INFINITY)
+                .total();
+            if reached <= 0.0 {
+                continue;
+            }
+            let share = f64::min(invite.size / reached, 1.0);
+
+            let draw_type = draw_types.entry(code).or_default();
+            if let Some(previous) = last.insert(code, invite.date) {
+                draw_type
+                    .gaps
+                    .push((invite.date - previous).num_days() as u64);
+            }
+            draw_type.invites.push(*invite);
+            draw_type.shares.push(share);
+        }
+        draw_types.retain(|_, draw_type| !draw_type.gaps.is_empty());
+
+        let mut pool = ScorePool::from(latest).with_density(density);
+        let mut today = latest.date;
+        for invite in invite_data
+            .iter()
+            .filter(|invite| invite.date > latest.date)
+        {
+            let days = (invite.date - today).num_days() as f64;
+            pool = (pool + inflow * days).clamped();
+            pool = (pool - pool.invite(invite)).clamped();
+            today = invite.date;
+        }
+
+        Some(Self {
+            date,
+            pool,
+            inflow,
+            draw_types,
+        })
+    }
+
+    /// the draw types the runs resample
+    pub fn codes(&self) -> Vec<CategoryCode> {
+        self.draw_types.keys().copied().collect()
+    }
+
+    // the candidates of `pool` the pathways of `invite` are open to
+    fn open_to(pool: ScorePool, invite: &Invite) -> ScorePool {
+        if invite.pathway == Pathway::from_codes(&[PathwayCode::Pnp]) {
+            pool.pnp()
+        } else if invite.pathway.is_pnp() {
+            pool
+        } else {
+            pool.non_pnp()
+        }
+    }
+
+    pub fn run(&self, config: &SimulationConfig) -> Simulation {
+        let mut rng = SeededRng::new(config.seed);
+        let mut draws = Vec::new();
+        for run in 0..config.runs {
+            self.run_once(run, config.days, &mut rng, &mut draws);
+        }
+        Simulation {
+            date: self.date,
+            runs: config.runs,
+            draws,
+        }
+    }
+
+    fn run_once(&self, run: usize, days: u32, rng: &mut SeededRng, draws: &mut Vec<SimulatedDraw>) {
+        // the first draw of each type follows its latest one by a resampled gap
+        let mut next: BTreeMap<CategoryCode, NaiveDate> = BTreeMap::new();
+        for (code, draw_type) in &self.draw_types {
+            let mut date = draw_type.invites.last().unwrap().date + draw_type.gap(rng);
+            while date <= self.date {
+                date = date + draw_type.gap(rng);
+            }
+            next.insert(*code, date);
+        }
+
+        let mut pool = self.pool;
+        for t in 1..=days as u64 {
+            let date = self.date + Days::new(t);
+            pool = (pool + self.inflow).clamped();
+
+            for (code, draw_type) in &self.draw_types {
+                if next[code] != date {
+                    continue;
+                }
+                next.insert(*code, date + draw_type.gap(rng));
+
+                let k = rng.below(draw_type.invites.len());
+                let template = draw_type.invites[k];
+                let open = Self::open_to(pool, &template);
+                if open.total() <= 0.0 {
+                    // nobody left to invite, e.g. the nominees were drained
+                    continue;
+                }
+                let score = open.score_for_count(template.size / draw_type.shares[k]);
+                let invite = Invite {
+                    date,
+                    score,
+                    ..template
+                };
+                pool = (pool - pool.invite(&invite)).clamped();
+
+                draws.push(SimulatedDraw {
+                    run,
+                    date,
+                    code: *code,
+                    size: invite.size,
+                    score,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DrawSimulator, SimulationConfig};
+    use crate::analyze::calc::ScoreDensity;
+    use crate::analyze::rate::RateConfig;
+    use crate::data::{test_raw_data, CategoryCode, Invite, Pool};
+
+    #[tokio::test]
+    async fn run() {
+        let invite_data = Invite::parse_all(test_raw_data().await).unwrap();
+        let pool_data = Pool::parse_all(test_raw_data().await).unwrap();
+        let simulator = DrawSimulator::new(
+            &pool_data,
+            &invite_data,
+            ScoreDensity::Uniform,
+            &RateConfig::default(),
+        )
+        .unwrap();
+        let config = SimulationConfig {
+            runs: 50,
+            ..Default::default()
+        };
+
+        // an invitation of unknown size leaves the inflow unknown
+        let mut nan = invite_data.clone();
+        let latest = pool_data.last().unwrap().date;
+        let i = nan.iter().rposition(|invite| invite.date < latest).unwrap();
+        nan[i].size = f64::NAN;
+        assert!(DrawSimulator::new(
+            &pool_data,
+            &nan,
+            ScoreDensity::Uniform,
+            &RateConfig::default()
+        )
+        .is_none());
+
+        let simulation = simulator.run(&config);
+        assert_eq!(simulation, simulator.run(&config));
+        assert_ne!(
+            simulation,
+            simulator.run(&SimulationConfig { seed: 1, ..config })
+        );
+        // caught up with the draws after the latest pool
+        assert!(simulation.date > pool_data.last().unwrap().date);
+        assert_eq!(simulation.date, invite_data.last().unwrap().date);
+        assert!(simulation.draws.iter().all(|draw| draw.run < config.runs
+            && draw.date > simulation.date
+            && (draw.date - simulation.date).num_days() <= config.days as i64));
+        assert!(simulation
+            .codes()
+            .iter()
+            .all(|code| simulator.codes().contains(code)));
+
+        let general = simulation.cutoffs(CategoryCode::GENERAL);
+        assert!(general.draws_per_run() >= 1.0);
+        let (p10, p50, p90) = (
+            general.quantile(0.1).unwrap(),
+            general.quantile(0.5).unwrap(),
+            general.quantile(0.9).unwrap(),
+        );
+        assert!(p10 <= p50 && p50 <= p90);
+        assert!((500.0..=570.0).contains(&p50));
+
+        // provincial nominees score above 600
+        let province = simulation.cutoffs(CategoryCode::PROVINCE);
+        assert!(province.quantile(0.0).unwrap() >= 600.0);
+
+        let empty = simulator.run(&SimulationConfig { runs: 0, ..config });
+        assert!(empty.draws.is_empty());
+        assert_eq!(empty.cutoffs(CategoryCode::GENERAL).quantile(0.5), None);
+        assert_eq!(empty.cutoffs(CategoryCode::GENERAL).draws_per_run(), 0.0);
+    }
+}
@@ -2,12 +2,13 @@ pub struct Smoother;
 use std::ops::{Add, Mul};
 use chrono::NaiveDate;
 use super::calc::ScorePool;
+use crate::error::AnalyzerError;
 
-pub(crate) trait SmoothLabel: Copy {
+pub trait SmoothLabel: Copy {
     fn steps(self, other: Self) -> i64;
 }
 
-pub(crate) trait SmoothValue: Add<Output = Self> + Mul<f64, Output = Self> + Copy {}
+pub trait SmoothValue: Add<Output = Self> + Mul<f64, Output = Self> + Copy {}
 
 impl SmoothLabel for NaiveDate {
     fn steps(self, other: Self) -> i64 {
@@ -15,9 +16,98 @@ impl SmoothLabel for NaiveDate {
     }
 }
 
+impl SmoothValue for f64 {}
 impl SmoothValue for ScorePool {}
 
+/// A smoother of `Smoother` and its parameters. Windows are in steps of the
+/// labels, days for dates, centered on each label.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    Exponential { alpha: f64 },
+    MovingAverage { window: u32 },
+    SavitzkyGolay { window: u32, degree: u32 },
+    Loess { window: u32 },
+    /// `ratio` of the variance the level drifts by per step to the variance
+    /// of the observations
+    Kalman { ratio: f64 },
+}
+
+impl std::fmt::Display for Smoothing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exponential { alpha } => write!(f, "exponential:{}", alpha),
+            Self::MovingAverage { window } => write!(f, "moving-average:{}", window),
+            Self::SavitzkyGolay { window, degree } => {
+                write!(f, "savitzky-golay:{}:{}", window, degree)
+            }
+            Self::Loess { window } => write!(f, "loess:{}", window),
+            Self::Kalman { ratio } => write!(f, "kalman:{}", ratio),
+        }
+    }
+}
+
+/// `exponential:<alpha>`, `moving-average:<window>`,
+/// `savitzky-golay:<window>:<degree>`, `loess:<window>` or `kalman:<ratio>`,
+/// as displayed
+impl std::str::FromStr for Smoothing {
+    type Err = AnalyzerError;
+
+    fn from_str(x: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let mut parts = x.split(':').map(str::trim);
+            let smoothing = match parts.next()? {
+                "exponential" => Self::Exponential {
+                    alpha: parts.next()?.parse().ok()?,
+                },
+                "moving-average" => Self::MovingAverage {
+                    window: parts.next()?.parse().ok()?,
+                },
+                "savitzky-golay" => Self::SavitzkyGolay {
+                    window: parts.next()?.parse().ok()?,
+                    degree: parts.next()?.parse().ok()?,
+                },
+                "loess" => Self::Loess {
+                    window: parts.next()?.parse().ok()?,
+                },
+                "kalman" => Self::Kalman {
+                    ratio: parts.next()?.parse().ok()?,
+                },
+                _ => return None,
+            };
+            let valid = match smoothing {
+                Self::Exponential { alpha } => 0.0 < alpha && alpha <= 1.0,
+                Self::MovingAverage { window } | Self::Loess { window } => window > 0,
+                Self::SavitzkyGolay { window, degree } => {
+                    window > 0 && degree <= Smoother::MAX_DEGREE
+                }
+                Self::Kalman { ratio } => ratio > 0.0,
+            };
+            (valid && parts.next().is_none()).then_some(smoothing)
+        };
+        parse().ok_or(AnalyzerError::Format(format!("invalid smoothing {:?}", x)))
+    }
+}
+
 impl Smoother {
+    /// highest degree of the polynomials of `savitzky_golay`
+    pub const MAX_DEGREE: u32 = 4;
+
+    pub fn smooth<L, D>(labels: &[L], values: &mut [D], smoothing: Smoothing)
+    where
+        L: SmoothLabel,
+        D: SmoothValue,
+    {
+        match smoothing {
+            Smoothing::Exponential { alpha } => Self::exponential(labels, values, alpha),
+            Smoothing::MovingAverage { window } => Self::moving_average(labels, values, window),
+            Smoothing::SavitzkyGolay { window, degree } => {
+                Self::savitzky_golay(labels, values, window, degree)
+            }
+            Smoothing::Loess { window } => Self::loess(labels, values, window),
+            Smoothing::Kalman { ratio } => Self::kalman(labels, values, ratio),
+        }
+    }
+
     pub fn exponential<L, D>(labels: &[L], values: &mut [D], alpha: f64)
     where
         L: SmoothLabel,
@@ -35,11 +125,298 @@ impl Smoother {
             let a = alpha;
             let b = (1.0 - alpha).powi(steps as i32);
             let r = 1.0 - a - b;
-            assert!(a + b + r == 1.0);
 
             let tmp_value = values[k];
             values[k] = values[k] * a + values[k - 1] * b + value_prev * r;
             value_prev = tmp_value;
         }
     }
+
+    /// The mean of the values within `window` steps centered on each label.
+    pub fn moving_average<L, D>(labels: &[L], values: &mut [D], window: u32)
+    where
+        L: SmoothLabel,
+        D: SmoothValue,
+    {
+        Self::local_polynomial(labels, values, window, 0, |_| 1.0);
+    }
+
+    /// The least squares polynomial of `degree` through the values within
+    /// `window` steps centered on each label, at the label. Labels need not
+    /// be evenly spaced.
+    pub fn savitzky_golay<L, D>(labels: &[L], values: &mut [D], window: u32, degree: u32)
+    where
+        L: SmoothLabel,
+        D: SmoothValue,
+    {
+        let degree = degree.min(Self::MAX_DEGREE);
+        Self::local_polynomial(labels, values, window, degree, |_| 1.0);
+    }
+
+    /// The local line through the values within `window` steps centered on
+    /// each label, weighted by the tricube of their distance to the label.
+    pub fn loess<L, D>(labels: &[L], values: &mut [D], window: u32)
+    where
+        L: SmoothLabel,
+        D: SmoothValue,
+    {
+        let tricube = |x: f64| (1.0 - x.abs().powi(3)).powi(3);
+        Self::local_polynomial(labels, values, window, 1, tricube);
+    }
+
+    /// The level of a random walk observed with noise, estimated from the
+    /// values before each label by the Kalman filter, then from the values
+    /// after it by the Rauch-Tung-Striebel smoother, so turns of the level
+    /// show up without lag.
+    pub fn kalman<L, D>(labels: &[L], values: &mut [D], ratio: f64)
+    where
+        L: SmoothLabel,
+        D: SmoothValue,
+    {
+        assert!(labels.len() == values.len());
+        if labels.is_empty() {
+            return;
+        }
+
+        // variances relative to the variance of the observations
+        let mut variance = 1.0;
+        let mut predicted = vec![1.0; values.len()];
+        let mut filtered = vec![1.0; values.len()];
+        for k in 1..values.len() {
+            let steps = labels[k].steps(labels[k - 1]) as f64;
+            predicted[k] = variance + ratio * steps;
+            let gain = predicted[k] / (predicted[k] + 1.0);
+            values[k] = values[k - 1] * (1.0 - gain) + values[k] * gain;
+            variance = (1.0 - gain) * predicted[k];
+            filtered[k] = variance;
+        }
+        for k in (0..values.len() - 1).rev() {
+            let gain = filtered[k] / predicted[k + 1];
+            values[k] = values[k] * (1.0 - gain) + values[k + 1] * gain;
+        }
+    }
+
+    // Replaces each value with the value at its label of the polynomial of
+    // `degree` fitted by weighted least squares to the values within `window`
+    // steps centered on it, weighted by `kernel` of their distance. The fit is
+    // a weighted sum of the values, found on the steps alone, so `SmoothValue`
+    // needs neither zero nor subtraction. The degree is lowered where there
+    // are too few values to fit it.
+    fn local_polynomial<L, D>(
+        labels: &[L],
+        values: &mut [D],
+        window: u32,
+        degree: u32,
+        kernel: impl Fn(f64) -> f64,
+    ) where
+        L: SmoothLabel,
+        D: SmoothValue,
+    {
+        assert!(labels.len() == values.len());
+        let half = f64::max(window as f64 / 2.0, 1.0);
+        let source = values.to_vec();
+        for k in 0..values.len() {
+            // the values within the window, their steps from the label scaled
+            // to -1 ..= 1, and their weights
+            let neighbors: Vec<(usize, f64, f64)> = (0..source.len())
+                .map(|j| (j, labels[j].steps(labels[k]) as f64))
+                .filter(|(_, x)| x.abs() <= window as f64 / 2.0)
+                .map(|(j, x)| (j, x / half, kernel(x / half)))
+                .filter(|(_, _, w)| *w > 0.0)
+                .collect();
+
+            let coefficients = (0..=degree as usize)
+                .rev()
+                .find_map(|degree| Self::intercept_row(&neighbors, degree));
+            let Some(coefficients) = coefficients else {
+                continue;
+            };
+            values[k] = neighbors
+                .iter()
+                .map(|(j, x, w)| {
+                    let basis: f64 = coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c);
+                    source[*j] * (w * basis)
+                })
+                .reduce(|a, b| a + b)
+                .unwrap();
+        }
+    }
+
+    // The row of the inverse of the normal matrix of a polynomial of `degree`
+    // that gives the intercept, or none if the matrix is singular.
+    fn intercept_row(neighbors: &[(usize, f64, f64)], degree: usize) -> Option<Vec<f64>> {
+        let n = degree + 1;
+        let moment = |p: usize| {
+            neighbors
+                .iter()
+                .map(|(_, x, w)| w * x.powi(p as i32))
+                .sum::<f64>()
+        };
+        let identity = |r: usize| (0..n).map(move |c| if r == c { 1.0 } else { 0.0 });
+        let mut matrix: Vec<Vec<f64>> = (0..n)
+            .map(|r| (0..n).map(|c| moment(r + c)).chain(identity(r)).collect())
+            .collect();
+        // x is within -1 ..= 1, so the pivots are compared with the weights
+        let tolerance = 1e-9 * moment(0);
+        for c in 0..n {
+            let pivot = (c..n).max_by(|a, b| matrix[*a][c].abs().total_cmp(&matrix[*b][c].abs()))?;
+            if matrix[pivot][c].abs() <= tolerance {
+                return None;
+            }
+            matrix.swap(c, pivot);
+            let scale = matrix[c][c];
+            matrix[c].iter_mut().for_each(|v| *v /= scale);
+            let row = matrix[c].clone();
+            for r in (0..n).filter(|r| *r != c) {
+                let factor = matrix[r][c];
+                for (v, p) in matrix[r].iter_mut().zip(&row) {
+                    *v -= factor * p;
+                }
+            }
+        }
+        Some(matrix[0][n..].to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Smoother, Smoothing};
+    use chrono::{Days, NaiveDate};
+
+    // irregularly spaced dates
+    fn labels() -> Vec<NaiveDate> {
+        let first = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        (0..40u64)
+            .map(|k| first + Days::new(k * 3 + k % 4))
+            .collect()
+    }
+
+    fn days(labels: &[NaiveDate]) -> Vec<f64> {
+        labels
+            .iter()
+            .map(|label| (*label - labels[0]).num_days() as f64)
+            .collect()
+    }
+
+    fn assert_near(actual: &[f64], expected: &[f64]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn exponential() {
+        // 24 days apart, where a + b + r rounds off 1.0 with alpha 2 / 61
+        let first = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let labels = [first, first + Days::new(24), first + Days::new(48)];
+        let mut values = vec![5.0; labels.len()];
+        Smoother::exponential(&labels, &mut values, 2.0 / (60.0 + 1.0));
+        assert_near(&values, &[5.0; 3]);
+    }
+
+    #[test]
+    fn irregular() {
+        let first = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let labels: Vec<_> = [0, 1, 25, 55, 116, 191, 271, 278, 341, 383]
+            .into_iter()
+            .map(|k| first + Days::new(k))
+            .collect();
+        for smoothing in [
+            Smoothing::Exponential { alpha: 0.3 },
+            Smoothing::Exponential { alpha: 0.05 },
+            Smoothing::Exponential { alpha: 2.0 / 61.0 },
+            Smoothing::MovingAverage { window: 45 },
+            Smoothing::SavitzkyGolay { window: 90, degree: 2 },
+            Smoothing::Loess { window: 70 },
+            Smoothing::Kalman { ratio: 0.037 },
+        ] {
+            let mut values = vec![3.7; labels.len()];
+            Smoother::smooth(&labels, &mut values, smoothing);
+            assert_near(&values, &[3.7; 10]);
+        }
+    }
+
+    #[test]
+    fn local_polynomial() {
+        let labels = labels();
+        let x = days(&labels);
+
+        let line: Vec<_> = x.iter().map(|x| 2.0 * x + 5.0).collect();
+        let mut values = line.clone();
+        Smoother::loess(&labels, &mut values, 20);
+        assert_near(&values, &line);
+
+        let quadratic: Vec<_> = x.iter().map(|x| 0.1 * x * x - x + 3.0).collect();
+        let mut values = quadratic.clone();
+        Smoother::savitzky_golay(&labels, &mut values, 20, 2);
+        assert_near(&values, &quadratic);
+        // a line cannot follow the curve
+        let mut values = quadratic.clone();
+        Smoother::savitzky_golay(&labels, &mut values, 20, 1);
+        assert!(values.iter().zip(&quadratic).any(|(a, e)| (a - e).abs() > 1.0));
+
+        let constant = vec![7.0; labels.len()];
+        let mut values = constant.clone();
+        Smoother::moving_average(&labels, &mut values, 30);
+        assert_near(&values, &constant);
+
+        // too few values within the window to fit the degree
+        let mut values = quadratic.clone();
+        Smoother::savitzky_golay(&labels, &mut values, 1, 4);
+        assert_near(&values, &quadratic);
+    }
+
+    #[test]
+    fn kalman() {
+        let labels = labels();
+        let constant = vec![7.0; labels.len()];
+        let mut values = constant.clone();
+        Smoother::kalman(&labels, &mut values, 0.01);
+        assert_near(&values, &constant);
+
+        // a step up in the middle
+        let step: Vec<_> = (0..labels.len())
+            .map(|k| if k < 20 { 0.0 } else { 10.0 })
+            .collect();
+        let mut filtered = step.clone();
+        Smoother::exponential(&labels, &mut filtered, 0.1);
+        let mut smoothed = step.clone();
+        Smoother::kalman(&labels, &mut smoothed, 0.1);
+        assert!(smoothed.iter().all(|x| (0.0..=10.0).contains(x)));
+        assert!(smoothed.windows(2).all(|x| x[0] <= x[1]));
+        // the smoothed level turns before the step, and is closer after it
+        assert!(smoothed[19] > 0.0 && filtered[19] == 0.0);
+        assert!(10.0 - smoothed[24] < 10.0 - filtered[24]);
+    }
+
+    #[test]
+    fn smoothing() {
+        for text in [
+            "exponential:0.1",
+            "moving-average:60",
+            "savitzky-golay:60:2",
+            "loess:45",
+            "kalman:0.01",
+        ] {
+            let smoothing: Smoothing = text.parse().unwrap();
+            assert_eq!(smoothing.to_string(), text);
+        }
+        for invalid in [
+            "exponential:0",
+            "moving-average:0",
+            "savitzky-golay:60:9",
+            "loess",
+            "kalman:-1",
+            "median:60",
+        ] {
+            assert!(invalid.parse::<Smoothing>().is_err(), "{}", invalid);
+        }
+
+        let labels = labels();
+        let values: Vec<_> = (0..labels.len()).map(|k| (k % 5) as f64).collect();
+        let (mut a, mut b) = (values.clone(), values);
+        Smoother::exponential(&labels, &mut a, 0.2);
+        Smoother::smooth(&labels, &mut b, Smoothing::Exponential { alpha: 0.2 });
+        assert_eq!(a, b);
+    }
 }
@@ -0,0 +1,929 @@
+use analyzer::analyze::backtest::{BacktestAnalyzer, ErrorMetrics};
+use analyzer::analyze::calc::ScoreDensity;
+use analyzer::analyze::category::CategoryAnalyzer;
+use analyzer::analyze::density::DensityAnalyzer;
+use analyzer::analyze::forecast::ForecastAnalyzer;
+use analyzer::analyze::pathway::{PathwayAnalyzer, PathwaySplit};
+use analyzer::analyze::plan::{PlanAnalyzer, PlanScope, PlanTarget};
+use analyzer::analyze::probability::{CandidateProfile, ProbabilityAnalyzer};
+use analyzer::analyze::rank::RankAnalyzer;
+use analyzer::analyze::rate::{RateAnalyzer, RateConfig};
+use analyzer::analyze::simulate::{DrawSimulator, SimulationConfig};
+use analyzer::data::{
+    raw_data, Archive, CategoryCode, CategoryRegistry, CategoryRound, DataSource, Invite, Pathway,
+    PathwayCode, Pool, SnapshotDiff,
+};
+use analyzer::error::AnalyzerError;
+use chrono::{Months, NaiveDate, Utc};
+use serde::Serialize;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+usage: ee-analyzer <command> [options]
+
+commands:
+    draws           list all invitation rounds
+    pool            list all pool distributions
+    rate            projected pool increase rate per score range
+    categories      invitations per category
+    pathways        invitations per set of eligible pathways, e.g. CEC only, or per
+                    pathway with --split
+    rank            candidates ahead of --score in the latest pool on or before --date
+    probability     chance of an ITA within 30 to 180 days for --score, --pathways and
+                    --eligible
+    simulate        cutoffs of the draws of each category over the next 180 days,
+                    from --runs simulated futures
+    forecast        cutoff and size of the next draw of each category, with 80%
+                    bands from --runs simulated futures
+    plan            invitations against the IRCC levels plan per year, or per month of
+                    --year, as of --date, without PNP draws which have their own plan
+    pace            what is left of the plan of the year of --date, and the invitations
+                    per week and month, draw sizes and draws it takes to meet it
+    backtest        errors of `forecast` cutoffs and of the `rate` pool projection,
+                    replayed since --date with the data before each draw or pool
+    density         candidates above the cutoff of general draws, as estimated with
+                    uniform and fitted densities within score ranges, against the sizes
+    archive         store the current document in --archive and list its snapshots
+    diff            changes from the --as-of snapshot (or the one before the latest)
+                    to the latest snapshot in --archive
+
+options:
+    --source <url|path>     IRCC ee_rounds_123_en.json, defaults to canada.ca
+    --year <year>           category year for `categories`, calendar year for `pathways`
+                            and `plan`, defaults to all
+    --round <yyyy-n>        category round for `categories`, e.g. 2024-1, instead of --year
+    --categories <path>     category config, defaults to the built-in categories.json
+    --plan <path>           levels plan config, defaults to the built-in plan.json
+    --score <crs>           CRS score for `rank`
+    --date <yyyy-mm-dd>     date for `rank`, `plan` and `pace`, defaults to today, first date for
+                            `backtest`, defaults to a year before the latest draw
+    --pathways <programs>   programs for `probability`, e.g. CEC+FSW, defaults to all
+    --split <split>         how `pathways` splits draws open to several pathways, even or
+                            weighted:<PNP>,<CEC>,<FSW>,<FST>, e.g. weighted:0,2,1,1
+    --eligible <keys>       occupation categories for `probability`, e.g. stem,french
+    --runs <n>              simulated futures for `simulate` and `forecast`, defaults to 200
+    --seed <n>              random seed for `simulate` and `forecast`, defaults to 0
+    --removal <kernel>      when invited candidates leave the pool, for `rate`, `backtest`
+                            and the pool inflow of `probability`, `simulate` and `forecast`,
+                            uniform:<days>, exponential:<mean days>:<days> or
+                            histogram:<bin days>:<share>,<share>,..., defaults to uniform:15
+    --acceptance <share>    share of the invited candidates who accept, for the same
+                            commands as --removal, defaults to 1
+    --rate-window <n>       latest rates averaged into the projected rate, for the same
+                            commands as --removal, defaults to 181
+    --archive <dir>         local snapshot archive of the IRCC document
+    --as-of <yyyy-mm-dd>    analyze the latest snapshot in --archive on or before the date
+    --json                  print json instead of a table
+";
+
+struct Args {
+    command: String,
+    source: DataSource,
+    year: i32,
+    round: Option<CategoryRound>,
+    score: Option<f64>,
+    date: Option<NaiveDate>,
+    pathway: Pathway,
+    split: Option<PathwaySplit>,
+    eligible: Vec<CategoryCode>,
+    simulation: SimulationConfig,
+    rate: RateConfig,
+    plan: PlanAnalyzer,
+    archive: Option<Archive>,
+    as_of: Option<NaiveDate>,
+    json: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command = None;
+        let mut source = DataSource::default();
+        let mut year = 0;
+        let mut round = None;
+        let mut score = None;
+        let mut date = None;
+        let mut pathway = "PNP+CEC+FSW+FST".parse().unwrap();
+        let mut split = None;
+        let mut eligible: Vec<String> = Vec::new();
+        let mut simulation = SimulationConfig::default();
+        let mut rate = RateConfig::default();
+        let mut plan = PlanAnalyzer::default();
+        let mut archive = None;
+        let mut as_of = None;
+        let mut json = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--source" => {
+                    let value = args.next().ok_or("missing value for --source")?;
+                    source = if value.starts_with("http://") || value.starts_with("https://") {
+                        DataSource::Url(value)
+                    } else {
+                        DataSource::File(value.into())
+                    };
+                }
+                "--year" => {
+                    let value = args.next().ok_or("missing value for --year")?;
+                    year = value
+                        .parse()
+                        .map_err(|_| format!("invalid year {}", value))?;
+                }
+                "--round" => {
+                    let value = args.next().ok_or("missing value for --round")?;
+                    round = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid round {}", value))?,
+                    );
+                }
+                "--score" => {
+                    let value = args.next().ok_or("missing value for --score")?;
+                    score = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid score {}", value))?,
+                    );
+                }
+                "--date" => {
+                    let value = args.next().ok_or("missing value for --date")?;
+                    date = Some(
+                        NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                            .map_err(|_| format!("invalid date {}", value))?,
+                    );
+                }
+                "--pathways" => {
+                    let value = args.next().ok_or("missing value for --pathways")?;
+                    pathway = value
+                        .parse()
+                        .map_err(|_| format!("invalid pathways {}", value))?;
+                }
+                "--split" => {
+                    let value = args.next().ok_or("missing value for --split")?;
+                    split = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid split {}", value))?,
+                    );
+                }
+                "--eligible" => {
+                    let value = args.next().ok_or("missing value for --eligible")?;
+                    eligible = value.split(',').map(|key| key.trim().to_string()).collect();
+                }
+                "--runs" => {
+                    let value = args.next().ok_or("missing value for --runs")?;
+                    simulation.runs = value
+                        .parse()
+                        .map_err(|_| format!("invalid runs {}", value))?;
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("missing value for --seed")?;
+                    simulation.seed = value
+                        .parse()
+                        .map_err(|_| format!("invalid seed {}", value))?;
+                }
+                "--removal" => {
+                    let value = args.next().ok_or("missing value for --removal")?;
+                    rate.removal = value
+                        .parse()
+                        .map_err(|_| format!("invalid removal kernel {}", value))?;
+                }
+                "--acceptance" => {
+                    let value = args.next().ok_or("missing value for --acceptance")?;
+                    rate.acceptance = value
+                        .parse()
+                        .ok()
+                        .filter(|share| (0.0..=1.0).contains(share))
+                        .ok_or(format!("invalid acceptance {}", value))?;
+                }
+                "--rate-window" => {
+                    let value = args.next().ok_or("missing value for --rate-window")?;
+                    rate.projection_window = value
+                        .parse()
+                        .map_err(|_| format!("invalid rate window {}", value))?;
+                }
+                "--categories" => {
+                    let value = args.next().ok_or("missing value for --categories")?;
+                    let text = std::fs::read_to_string(&value)
+                        .map_err(|e| format!("cannot read {}: {}", value, e))?;
+                    CategoryRegistry::from_json(&text)
+                        .map_err(|e| e.to_string())?
+                        .install();
+                }
+                "--plan" => {
+                    let value = args.next().ok_or("missing value for --plan")?;
+                    let text = std::fs::read_to_string(&value)
+                        .map_err(|e| format!("cannot read {}: {}", value, e))?;
+                    plan = PlanAnalyzer::from_json(&text).map_err(|e| e.to_string())?;
+                }
+                "--archive" => {
+                    let value = args.next().ok_or("missing value for --archive")?;
+                    archive = Some(Archive::new(value));
+                }
+                "--as-of" => {
+                    let value = args.next().ok_or("missing value for --as-of")?;
+                    as_of = Some(
+                        NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                            .map_err(|_| format!("invalid date {}", value))?,
+                    );
+                }
+                "--json" => json = true,
+                _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+                _ if command.is_none() => command = Some(arg),
+                _ => return Err(format!("unexpected argument {}", arg)),
+            }
+        }
+
+        Ok(Self {
+            command: command.ok_or("missing command")?,
+            source,
+            year,
+            round,
+            score,
+            date,
+            pathway,
+            split,
+            eligible: eligible
+                .iter()
+                .map(|key| CategoryCode::from_key(key).ok_or(format!("invalid category {}", key)))
+                .collect::<Result<_, _>>()?,
+            simulation,
+            rate,
+            plan,
+            archive,
+            as_of,
+            json,
+        })
+    }
+}
+
+/// Rows rendered either as an aligned table or as a json array of objects.
+struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<Cell>>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Cell {
+    Text(String),
+    Number(f64),
+    /// printed as blank, null in json
+    Empty,
+}
+
+impl std::fmt::Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(x) => x.fmt(f),
+            Self::Number(x) if x.fract() == 0.0 => write!(f, "{}", x),
+            Self::Number(x) => write!(f, "{:.3}", x),
+            Self::Empty => Ok(()),
+        }
+    }
+}
+
+impl Table {
+    fn print(&self, json: bool) {
+        if json {
+            // keep the column order, a `serde_json::Map` would sort the keys
+            let rows: Vec<_> = self
+                .rows
+                .iter()
+                .map(|row| {
+                    let fields: Vec<_> = self
+                        .header
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(key, cell)| {
+                            format!(
+                                "{}: {}",
+                                serde_json::to_string(key).unwrap(),
+                                serde_json::to_string(cell).unwrap()
+                            )
+                        })
+                        .collect();
+                    format!("  {{{}}}", fields.join(", "))
+                })
+                .collect();
+            println!("[\n{}\n]", rows.join(",\n"));
+            return;
+        }
+
+        let rows: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+            .collect();
+        let widths: Vec<usize> = (0..self.header.len())
+            .map(|i| {
+                rows.iter()
+                    .map(|row| row[i].len())
+                    .chain([self.header[i].len()])
+                    .max()
+                    .unwrap()
+            })
+            .collect();
+
+        for row in [&self.header].into_iter().chain(rows.iter()) {
+            let line: Vec<_> = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            println!("{}", line.join("  "));
+        }
+    }
+}
+
+fn draws(invite_data: &[Invite]) -> Table {
+    Table {
+        header: ["draw", "date", "category", "size", "score", "tie break"]
+            .map(String::from)
+            .into(),
+        rows: invite_data
+            .iter()
+            .map(|invite| {
+                vec![
+                    Cell::Text(invite.id.to_string()),
+                    Cell::Text(invite.date.format("%Y-%m-%d").to_string()),
+                    Cell::Text(invite.category.as_str()),
+                    Cell::Number(invite.size),
+                    Cell::Number(invite.score),
+                    Cell::Text(
+                        invite
+                            .tie_break
+                            .map(|x| x.format("%Y-%m-%d %H:%M:%S").to_string())
+                            .unwrap_or_default(),
+                    ),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn pool(pool_data: &[Pool]) -> Table {
+    let schema = Pool::schema_of(pool_data);
+    Table {
+        header: ["date".into(), "total".into()]
+            .into_iter()
+            .chain((0..schema.len()).rev().map(|i| schema.as_str(i)))
+            .collect(),
+        rows: pool_data
+            .iter()
+            .map(|pool| {
+                [
+                    Cell::Text(pool.date.format("%Y-%m-%d").to_string()),
+                    Cell::Number(pool.total()),
+                ]
+                .into_iter()
+                .chain((0..schema.len()).rev().map(|i| Cell::Number(pool[i])))
+                .collect()
+            })
+            .collect(),
+    }
+}
+
+fn rate(pool_data: &[Pool], invite_data: &[Invite], config: &RateConfig) -> Table {
+    let projected_rate = RateAnalyzer::projected_inflow(pool_data, invite_data, config);
+
+    Table {
+        header: ["score", "per day"].map(String::from).into(),
+        rows: (0..projected_rate.len())
+            .rev()
+            .map(|i| {
+                vec![
+                    Cell::Text(projected_rate.schema().as_str(i)),
+                    Cell::Number(projected_rate[i]),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn rank(
+    pool_data: &[Pool],
+    invite_data: &[Invite],
+    score: Option<f64>,
+    date: Option<NaiveDate>,
+) -> Result<Table, String> {
+    let score = score.ok_or("missing --score")?;
+    let date = date.unwrap_or(Utc::now().date_naive());
+    let density = DensityAnalyzer::fit(pool_data, invite_data);
+    let rank = RankAnalyzer::rank_for_score(pool_data, score, date, density)
+        .ok_or(format!("no pool on or before {}", date))?;
+
+    Ok(Table {
+        header: ["date", "score", "bucket", "ahead", "total", "percentile"]
+            .map(String::from)
+            .into(),
+        rows: vec![vec![
+            Cell::Text(rank.date.format("%Y-%m-%d").to_string()),
+            Cell::Number(rank.score),
+            Cell::Text(rank.bucket_label),
+            Cell::Number(rank.ahead.round()),
+            Cell::Number(rank.total),
+            Cell::Number(rank.percentile),
+        ]],
+    })
+}
+
+fn probability(
+    pool_data: &[Pool],
+    invite_data: &[Invite],
+    profile: CandidateProfile,
+    rate_config: &RateConfig,
+) -> Result<Table, String> {
+    let density = DensityAnalyzer::fit(pool_data, invite_data);
+    let probability = ProbabilityAnalyzer::ita_probability(
+        pool_data,
+        invite_data,
+        &profile,
+        density,
+        rate_config,
+    )
+    .ok_or("no pool data or inflow")?;
+    eprintln!(
+        "as of {}, {} ahead, median wait {}",
+        probability.date.format("%Y-%m-%d"),
+        probability.ahead.round(),
+        probability
+            .median_wait
+            .map(|days| format!("{} days", days))
+            .unwrap_or("beyond the horizon".into())
+    );
+
+    Ok(Table {
+        header: ["days", "probability"].map(String::from).into(),
+        rows: probability
+            .horizons
+            .iter()
+            .map(|horizon| {
+                vec![
+                    Cell::Number(horizon.days as f64),
+                    Cell::Number(horizon.probability),
+                ]
+            })
+            .collect(),
+    })
+}
+
+fn simulate(
+    pool_data: &[Pool],
+    invite_data: &[Invite],
+    config: SimulationConfig,
+    rate_config: &RateConfig,
+) -> Result<Table, String> {
+    let density = DensityAnalyzer::fit(pool_data, invite_data);
+    let simulator = DrawSimulator::new(pool_data, invite_data, density, rate_config)
+        .ok_or("no pool data or inflow")?;
+    let simulation = simulator.run(&config);
+    eprintln!(
+        "as of {}, {} runs of {} days",
+        simulation.date.format("%Y-%m-%d"),
+        simulation.runs,
+        config.days
+    );
+
+    Ok(Table {
+        header: ["category", "draws", "p10", "p50", "p90"]
+            .map(String::from)
+            .into(),
+        rows: simulation
+            .codes()
+            .into_iter()
+            .map(|code| {
+                let cutoffs = simulation.cutoffs(code);
+                let quantile = |q| Cell::Number(cutoffs.quantile(q).unwrap().round());
+                vec![
+                    Cell::Text(code.as_str()),
+                    Cell::Number(cutoffs.draws_per_run()),
+                    quantile(0.1),
+                    quantile(0.5),
+                    quantile(0.9),
+                ]
+            })
+            .collect(),
+    })
+}
+
+fn forecast(
+    pool_data: &[Pool],
+    invite_data: &[Invite],
+    config: SimulationConfig,
+    rate_config: &RateConfig,
+) -> Table {
+    let density = DensityAnalyzer::fit(pool_data, invite_data);
+    let forecasts =
+        ForecastAnalyzer::next_draws(pool_data, invite_data, density, rate_config, &config);
+
+    Table {
+        header: [
+            "date",
+            "category",
+            "chance",
+            "size",
+            "size low",
+            "size high",
+            "score",
+            "score low",
+            "score high",
+        ]
+        .map(String::from)
+        .into(),
+        rows: forecasts
+            .into_iter()
+            .map(|forecast| {
+                vec![
+                    Cell::Text(forecast.date.format("%Y-%m-%d").to_string()),
+                    Cell::Text(forecast.label),
+                    Cell::Number(forecast.likelihood),
+                    Cell::Number(forecast.size.mid),
+                    Cell::Number(forecast.size.low),
+                    Cell::Number(forecast.size.high),
+                    Cell::Number(forecast.score.mid.round()),
+                    Cell::Number(forecast.score.low.round()),
+                    Cell::Number(forecast.score.high.round()),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn plan(plan: &PlanAnalyzer, invite_data: &[Invite], year: i32, date: Option<NaiveDate>) -> Table {
+    let today = date.unwrap_or(Utc::now().date_naive());
+    let progress = if year == 0 {
+        plan.yearly(invite_data, PlanScope::NonPnp, today)
+    } else {
+        plan.monthly(invite_data, PlanScope::NonPnp, today)
+            .into_iter()
+            .filter(|x| x.year == year)
+            .collect()
+    };
+
+    Table {
+        header: ["year", "month", "plan min", "plan max", "invited"]
+            .map(String::from)
+            .into(),
+        rows: progress
+            .into_iter()
+            .map(|x| {
+                vec![
+                    Cell::Number(x.year as f64),
+                    x.month
+                        .map(|month| Cell::Number(month as f64))
+                        .unwrap_or(Cell::Empty),
+                    Cell::Number(x.plan_min.round()),
+                    Cell::Number(x.plan_max.round()),
+                    x.actual.map(Cell::Number).unwrap_or(Cell::Empty),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn pace(
+    plan: &PlanAnalyzer,
+    invite_data: &[Invite],
+    date: Option<NaiveDate>,
+) -> Result<Table, String> {
+    let today = date.unwrap_or(Utc::now().date_naive());
+    let pace = plan
+        .pace(invite_data, PlanScope::NonPnp, today)
+        .ok_or(format!("no plan for {}", today.format("%Y")))?;
+    eprintln!(
+        "as of {}, {} invited in {} draws, {} days and ~{:.1} draws left at the cadence so far",
+        pace.date.format("%Y-%m-%d"),
+        pace.invited,
+        pace.draws,
+        pace.days_left,
+        pace.draws_left
+    );
+
+    let number = |x: f64| Cell::Number(x.round());
+    let pair = |name: &str, target: Option<PlanTarget>| match target {
+        Some(target) => vec![
+            Cell::Text(name.into()),
+            number(target.min),
+            number(target.max),
+        ],
+        None => vec![Cell::Text(name.into()), Cell::Empty, Cell::Empty],
+    };
+    let date = |date: Option<NaiveDate>| {
+        date.map(|date| Cell::Text(date.format("%Y-%m-%d").to_string()))
+            .unwrap_or(Cell::Empty)
+    };
+    Ok(Table {
+        header: ["", "low", "high"].map(String::from).into(),
+        rows: vec![
+            pair("target", Some(pace.target)),
+            pair("remaining", Some(pace.remaining)),
+            pair("per week", Some(pace.per_week)),
+            pair("per month", Some(pace.per_month)),
+            pair("draw size", pace.draw_size),
+            pair("draws needed", pace.draws_needed),
+            vec![
+                Cell::Text("met on".into()),
+                date(pace.min_met_on),
+                date(pace.max_met_on),
+            ],
+        ],
+    })
+}
+
+fn backtest(
+    pool_data: &[Pool],
+    invite_data: &[Invite],
+    since: Option<NaiveDate>,
+    config: SimulationConfig,
+    rate_config: &RateConfig,
+) -> Result<Table, String> {
+    let latest = invite_data.last().ok_or("no invite data")?.date;
+    let since = since.unwrap_or(latest - Months::new(12));
+    let cutoffs =
+        BacktestAnalyzer::cutoffs(pool_data, invite_data, since, |pool_data, invite_data| {
+            // calibrated with the data before the draw, too
+            let density = DensityAnalyzer::fit(pool_data, invite_data);
+            ForecastAnalyzer::next_draws(pool_data, invite_data, density, rate_config, &config)
+        });
+    let rates = BacktestAnalyzer::rates(pool_data, invite_data, since, rate_config);
+    let schema = Pool::schema_of(pool_data);
+
+    let row = |model: &str, key: String, metrics: ErrorMetrics| {
+        vec![
+            Cell::Text(model.into()),
+            Cell::Text(key),
+            Cell::Number(metrics.count as f64),
+            Cell::Number(metrics.mae),
+            Cell::Number(metrics.rmse),
+            metrics.coverage.map(Cell::Number).unwrap_or(Cell::Empty),
+        ]
+    };
+    Ok(Table {
+        header: ["model", "key", "count", "mae", "rmse", "coverage"]
+            .map(String::from)
+            .into(),
+        rows: ErrorMetrics::per_key(&cutoffs)
+            .into_iter()
+            .map(|(code, metrics)| row("cutoff", code.as_str(), metrics))
+            .chain(
+                ErrorMetrics::per_key(&rates)
+                    .into_iter()
+                    .rev()
+                    .map(|(i, metrics)| row("rate", schema.as_str(i), metrics)),
+            )
+            .collect(),
+    })
+}
+
+fn density(pool_data: &[Pool], invite_data: &[Invite]) -> Table {
+    let samples = DensityAnalyzer::samples(pool_data, invite_data);
+    let fitted = DensityAnalyzer::calibrate(&samples);
+    if let ScoreDensity::Linear { steepness } = fitted {
+        eprintln!(
+            "steepness {:.2}, relative error uniform {:.3}, fitted {:.3}",
+            steepness,
+            DensityAnalyzer::error(&samples, ScoreDensity::Uniform),
+            DensityAnalyzer::error(&samples, fitted)
+        );
+    }
+
+    Table {
+        header: ["date", "score", "size", "uniform", "fitted"]
+            .map(String::from)
+            .into(),
+        rows: samples
+            .iter()
+            .map(|sample| {
+                vec![
+                    Cell::Text(sample.date.format("%Y-%m-%d").to_string()),
+                    Cell::Number(sample.score),
+                    Cell::Number(sample.size),
+                    Cell::Number(sample.estimate(ScoreDensity::Uniform).round()),
+                    Cell::Number(sample.estimate(fitted).round()),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn categories(
+    pool_data: &[Pool],
+    invite_data: &[Invite],
+    year: i32,
+    round: Option<CategoryRound>,
+) -> Result<Table, String> {
+    let invite_data = match round {
+        Some(round) => *CategoryAnalyzer::of_category_rounds(invite_data)
+            .get(&round)
+            .ok_or(format!("no category invitation in round {}", round))?,
+        None => *CategoryAnalyzer::of_category_years(invite_data)
+            .get(&year)
+            .ok_or(format!("no category invitation in {}", year))?,
+    };
+    let (_, category_invites, _) = CategoryAnalyzer::invite_per_category(pool_data, invite_data);
+    let total = category_invites.last().copied().unwrap_or_default();
+
+    Ok(Table {
+        header: ["category", "size", "percent"].map(String::from).into(),
+        rows: CategoryCode::values()
+            .iter()
+            .filter(|category| total[**category] != 0.0)
+            .map(|category| {
+                vec![
+                    Cell::Text(category.as_str()),
+                    Cell::Number(total[*category]),
+                    Cell::Number(total.normalize()[*category] * 100.0),
+                ]
+            })
+            .collect(),
+    })
+}
+
+fn pathways(
+    invite_data: &[Invite],
+    year: i32,
+    split: Option<PathwaySplit>,
+) -> Result<Table, String> {
+    let invite_data = *PathwayAnalyzer::of_years(invite_data)
+        .get(&year)
+        .ok_or(format!("no invitation in {}", year))?;
+    if let Some(split) = split {
+        let (_, pathway_invites) = PathwayAnalyzer::invite_per_pathway(invite_data, split);
+        let pathway_invite = pathway_invites.last().copied().unwrap_or_default();
+        return Ok(Table {
+            header: ["pathway", "size", "percent"].map(String::from).into(),
+            rows: PathwayCode::values()
+                .iter()
+                .map(|code| {
+                    vec![
+                        Cell::Text(code.as_str()),
+                        Cell::Number(pathway_invite[*code]),
+                        Cell::Number(pathway_invite.normalize()[*code] * 100.0),
+                    ]
+                })
+                .collect(),
+        });
+    }
+
+    let (_, pathway_invites) = PathwayAnalyzer::invite_per_draw_pathway(invite_data);
+    let total: f64 = pathway_invites
+        .values()
+        .filter_map(|values| values.last())
+        .sum();
+
+    Ok(Table {
+        header: ["pathways", "size", "percent"].map(String::from).into(),
+        rows: pathway_invites
+            .iter()
+            .map(|(pathway, values)| {
+                let size = values.last().copied().unwrap_or_default();
+                vec![
+                    Cell::Text(pathway.as_str()),
+                    Cell::Number(size),
+                    Cell::Number(size / total * 100.0),
+                ]
+            })
+            .collect(),
+    })
+}
+
+async fn archive(archive: &Archive, source: &DataSource) -> Result<Table, AnalyzerError> {
+    let latest = archive.fetch(source).await?;
+    eprintln!("stored {}", latest.path.display());
+
+    Ok(Table {
+        header: ["fetched", "hash", "path"].map(String::from).into(),
+        rows: archive
+            .snapshots()?
+            .iter()
+            .map(|snapshot| {
+                vec![
+                    Cell::Text(snapshot.fetched.format("%Y-%m-%d %H:%M:%S").to_string()),
+                    Cell::Text(format!("{:016x}", snapshot.hash)),
+                    Cell::Text(snapshot.path.display().to_string()),
+                ]
+            })
+            .collect(),
+    })
+}
+
+async fn diff(archive: &Archive, as_of: Option<NaiveDate>) -> Result<SnapshotDiff, String> {
+    let mut snapshots = archive.snapshots().map_err(|e| e.to_string())?;
+    let new = snapshots.pop().ok_or("no snapshot in the archive")?;
+    let old = match as_of {
+        Some(date) => archive
+            .as_of(date)
+            .map_err(|e| e.to_string())?
+            .ok_or(format!("no snapshot on or before {}", date))?,
+        None => snapshots.pop().ok_or("only one snapshot in the archive")?,
+    };
+    eprintln!("{} -> {}", old.path.display(), new.path.display());
+
+    let old = old.load().await.map_err(|e| e.to_string())?;
+    let new = new.load().await.map_err(|e| e.to_string())?;
+    Ok(SnapshotDiff::diff(&old, &new))
+}
+
+enum Output {
+    Table(Table),
+    Diff(SnapshotDiff),
+}
+
+impl Output {
+    fn print(&self, json: bool) {
+        match self {
+            Self::Table(table) => table.print(json),
+            Self::Diff(diff) if json => println!("{}", serde_json::to_string_pretty(diff).unwrap()),
+            Self::Diff(diff) => print!("{}", diff),
+        }
+    }
+}
+
+async fn run(args: Args) -> Result<Output, String> {
+    let source = match (&args.archive, args.as_of) {
+        (Some(archive), _) if args.command == "archive" => {
+            return self::archive(archive, &args.source)
+                .await
+                .map(Output::Table)
+                .map_err(|e| e.to_string());
+        }
+        (Some(archive), as_of) if args.command == "diff" => {
+            return self::diff(archive, as_of).await.map(Output::Diff);
+        }
+        (None, _) if ["archive", "diff"].contains(&args.command.as_str()) => {
+            return Err("missing --archive".into())
+        }
+        (Some(archive), Some(date)) => archive
+            .as_of(date)
+            .map_err(|e| e.to_string())?
+            .ok_or(format!("no snapshot on or before {}", date))?
+            .source(),
+        (None, Some(_)) => return Err("--as-of requires --archive".into()),
+        (_, None) => args.source,
+    };
+    let raw_data = raw_data(&source)
+        .await
+        .map_err(|e: AnalyzerError| e.to_string())?;
+    let (invite_data, errors) = Invite::parse_all_lenient(&raw_data);
+    errors
+        .iter()
+        .for_each(|error| eprintln!("skip invite: {}", error));
+    let (pool_data, errors) = Pool::parse_all_lenient(&raw_data);
+    errors
+        .iter()
+        .for_each(|error| eprintln!("skip pool: {}", error));
+
+    let table = match args.command.as_str() {
+        "draws" => draws(&invite_data),
+        "pool" => pool(&pool_data),
+        "rate" => rate(&pool_data, &invite_data, &args.rate),
+        "categories" => categories(&pool_data, &invite_data, args.year, args.round)?,
+        "pathways" => pathways(&invite_data, args.year, args.split)?,
+        "density" => density(&pool_data, &invite_data),
+        "rank" => rank(&pool_data, &invite_data, args.score, args.date)?,
+        "probability" => {
+            let profile = CandidateProfile {
+                score: args.score.ok_or("missing --score")?,
+                pathway: args.pathway,
+                categories: args.eligible,
+            };
+            probability(&pool_data, &invite_data, profile, &args.rate)?
+        }
+        "simulate" => simulate(&pool_data, &invite_data, args.simulation, &args.rate)?,
+        "forecast" => forecast(&pool_data, &invite_data, args.simulation, &args.rate),
+        "plan" => plan(&args.plan, &invite_data, args.year, args.date),
+        "pace" => pace(&args.plan, &invite_data, args.date)?,
+        "backtest" => backtest(
+            &pool_data,
+            &invite_data,
+            args.date,
+            args.simulation,
+            &args.rate,
+        )?,
+        command => return Err(format!("unknown command {}", command)),
+    };
+    Ok(Output::Table(table))
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let json = args.json;
+    match run(args).await {
+        Ok(output) => {
+            output.print(json);
+            ExitCode::SUCCESS
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -3,48 +3,109 @@ use super::utils::Stacker;
 use crate::analyze::category::CategoryAnalyzer;
 use crate::chart::dataset::{ChartData, LineDataset, Tooltip};
 use crate::chart::utils::{ToTimestamp, SERIALIZER};
-use crate::data::{CategoryCode, Invite, Pool};
+use crate::data::{CategoryCode, CategoryRound, Invite, InviteSet, Pool, PoolSet};
 use itertools::Itertools;
 use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
-pub fn wasm_category_years(invite_data: *const Vec<Invite>) -> JsValue {
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    CategoryAnalyzer::of_category_years(invite_data)
-        .keys()
-        .sorted()
-        .map(|year| Dropdown {
-            key: *year as f64,
-            label: if *year == 0 {
-                "all".into()
-            } else {
-                year.to_string()
-            },
-        })
-        .collect::<Vec<_>>()
-        .serialize(&SERIALIZER)
-        .unwrap_throw()
+impl InviteSet {
+    pub fn category_years(&self) -> JsValue {
+        let invite_data = &self[..];
+        CategoryAnalyzer::of_category_years(invite_data)
+            .keys()
+            .sorted()
+            .map(|year| Dropdown {
+                key: *year as f64,
+                label: if *year == 0 {
+                    "all".into()
+                } else {
+                    year.to_string()
+                },
+            })
+            .collect::<Vec<_>>()
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
+
+    pub fn category_rounds(&self) -> JsValue {
+        CategoryAnalyzer::of_category_rounds(self)
+            .keys()
+            .sorted()
+            .map(|round| {
+                let key = if *round == CategoryRound::default() {
+                    "all".to_string()
+                } else {
+                    round.to_string()
+                };
+                Dropdown {
+                    label: key.clone(),
+                    key,
+                }
+            })
+            .collect::<Vec<_>>()
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
 }
 
 #[wasm_bindgen]
-pub fn wasm_category_invite_data(
-    pool_data: *const Vec<Pool>,
-    invite_data: *const Vec<Invite>,
-    category_year: f64,
-    with_pnp: bool,
-) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    let invite_data = CategoryAnalyzer::of_category_years(invite_data)[&(category_year as i32)];
+impl PoolSet {
+    pub fn category_invite_data(
+        &self,
+        invite_data: &InviteSet,
+        category_year: f64,
+        with_pnp: bool,
+    ) -> JsValue {
+        let invite_data = CategoryAnalyzer::of_category_years(invite_data)[&(category_year as i32)];
+        category_invite_chart(self, invite_data, with_pnp)
+    }
+
+    pub fn category_round_invite_data(
+        &self,
+        invite_data: &InviteSet,
+        category_round: String,
+        with_pnp: bool,
+    ) -> JsValue {
+        let invite_data =
+            CategoryAnalyzer::of_category_rounds(invite_data)[&parse_round(&category_round)];
+        category_invite_chart(self, invite_data, with_pnp)
+    }
+
+    pub fn category_pool_data(&self, invite_data: &InviteSet, category_year: f64) -> JsValue {
+        let invite_data = CategoryAnalyzer::of_category_years(invite_data)[&(category_year as i32)];
+        category_pool_chart(self, invite_data)
+    }
+
+    pub fn category_round_pool_data(
+        &self,
+        invite_data: &InviteSet,
+        category_round: String,
+    ) -> JsValue {
+        let invite_data =
+            CategoryAnalyzer::of_category_rounds(invite_data)[&parse_round(&category_round)];
+        category_pool_chart(self, invite_data)
+    }
+}
+
+// "all" or a round such as "2024-1", as handed out by `category_rounds`
+fn parse_round(x: &str) -> CategoryRound {
+    if x == "all" {
+        CategoryRound::default()
+    } else {
+        x.parse().unwrap_throw()
+    }
+}
+
+fn category_invite_chart(pool_data: &[Pool], invite_data: &[Invite], with_pnp: bool) -> JsValue {
     let (category_invite_labels, mut category_invites, mut categories) =
         CategoryAnalyzer::invite_per_category(pool_data, invite_data);
 
     if !with_pnp {
         category_invites
             .iter_mut()
-            .for_each(|pool| pool[CategoryCode::Province] = 0.0);
-        categories.remove(&CategoryCode::Province);
+            .for_each(|pool| pool[CategoryCode::PROVINCE] = 0.0);
+        categories.remove(&CategoryCode::PROVINCE);
     }
 
     let labels: Vec<_> = category_invite_labels
@@ -54,13 +115,13 @@ pub fn wasm_category_invite_data(
 
     let datasets = categories
         .iter()
-        .sorted_by_key(|category| **category as usize)
+        .sorted_by_key(|category| category.index())
         .map(|category| {
             let data: Vec<_> = category_invites
                 .iter()
                 .map(|pool| pool.normalize() * 100.0)
                 .map(|pool| {
-                    Some(Stacker::<{ CategoryCode::N }, _>::new(pool).val(*category as usize))
+                    Some(Stacker::<{ CategoryCode::N }, _>::new(pool).val(category.index()))
                 })
                 .collect();
 
@@ -83,7 +144,7 @@ pub fn wasm_category_invite_data(
 
     let tooltip_label: Vec<_> = categories
         .iter()
-        .sorted_by_key(|category| **category as usize)
+        .sorted_by_key(|category| category.index())
         .map(|category| {
             category_invites
                 .iter()
@@ -111,15 +172,7 @@ pub fn wasm_category_invite_data(
     .unwrap_throw()
 }
 
-#[wasm_bindgen]
-pub fn wasm_category_pool_data(
-    pool_data: *const Vec<Pool>,
-    invite_data: *const Vec<Invite>,
-    category_year: f64,
-) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    let invite_data = CategoryAnalyzer::of_category_years(invite_data)[&(category_year as i32)];
+fn category_pool_chart(pool_data: &[Pool], invite_data: &[Invite]) -> JsValue {
     let (category_invite_labels, category_invites, categories) =
         CategoryAnalyzer::percent_per_category(pool_data, invite_data);
 
@@ -130,7 +183,7 @@ pub fn wasm_category_pool_data(
 
     let datasets = categories
         .iter()
-        .sorted_by_key(|category| **category as usize)
+        .sorted_by_key(|category| category.index())
         .map(|category| {
             let data: Vec<_> = category_invites
                 .iter()
@@ -161,7 +214,7 @@ pub fn wasm_category_pool_data(
 
     let tooltip_label: Vec<_> = categories
         .iter()
-        .sorted_by_key(|category| **category as usize)
+        .sorted_by_key(|category| category.index())
         .map(|category| {
             category_invites
                 .iter()
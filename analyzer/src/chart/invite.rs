@@ -1,6 +1,9 @@
+use crate::analyze::forecast::ForecastAnalyzer;
+use crate::analyze::simulate::SimulationConfig;
 use crate::chart::dataset::{BarDataset, ChartData, LineDataset, Tooltip};
-use crate::chart::utils::{ToTimestamp, SERIALIZER};
-use crate::data::{CategoryCode, Invite};
+use crate::chart::pool::WasmRateConfig;
+use crate::chart::utils::{fitted_density, ToTimestamp, SERIALIZER};
+use crate::data::{CategoryCode, InviteSet, PoolSet};
 use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
 use itertools::Itertools;
 use serde::Serialize;
@@ -8,193 +11,247 @@ use std::collections::HashMap;
 use std::sync::{Mutex, OnceLock};
 use wasm_bindgen::{prelude::*, throw_str};
 
-#[wasm_bindgen]
-pub fn wasm_invite_score_data(invite_data: *const Vec<Invite>) -> JsValue {
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    let labels: Vec<_> = invite_data
-        .iter()
-        .map(|invitation| invitation.date.to_timestamp() as f64)
-        .collect();
-    let datasets: Vec<_> = CategoryCode::values()
-        .iter()
-        .map(|category| {
-            let data: Vec<_> = invite_data
-                .iter()
-                .map(|invitation| {
-                    if invitation.category.code == *category {
-                        Some(invitation.score as f64)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
+impl InviteSet {
+    fn score_chart_data(&self) -> ChartData<LineDataset> {
+        let invite_data = &self[..];
+        let labels: Vec<_> = invite_data
+            .iter()
+            .map(|invitation| invitation.date.to_timestamp() as f64)
+            .collect();
+        let datasets: Vec<_> = CategoryCode::values()
+            .iter()
+            .map(|category| {
+                let data: Vec<_> = invite_data
+                    .iter()
+                    .map(|invitation| {
+                        if invitation.category.code == *category {
+                            Some(invitation.score)
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
 
-            LineDataset {
-                label: category.as_str(),
-                data,
-                background_color: category.as_color(),
-                border_color: category.as_color(),
-                ..Default::default()
-            }
-        })
-        .collect();
-    let tooltip_title: Vec<_> = invite_data
-        .iter()
-        .map(|invitation| format!("{} ({})", invitation.date.format("%Y-%m-%d"), invitation.id))
-        .collect();
-
-    ChartData {
-        labels,
-        datasets,
-        tooltip: Tooltip {
-            title: vec![tooltip_title],
-            label: Vec::new(),
-        },
+                LineDataset {
+                    label: category.as_str(),
+                    data,
+                    background_color: category.as_color(),
+                    border_color: category.as_color(),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let tooltip_title: Vec<_> = invite_data
+            .iter()
+            .map(|invitation| format!("{} ({})", invitation.date.format("%Y-%m-%d"), invitation.id))
+            .collect();
+
+        ChartData {
+            labels,
+            datasets,
+            tooltip: Tooltip {
+                title: vec![tooltip_title],
+                label: Vec::new(),
+            },
+        }
     }
-    .serialize(&SERIALIZER)
-    .unwrap_throw()
 }
 
 #[wasm_bindgen]
-pub fn wasm_invite_size_data(invite_data: *const Vec<Invite>, mode: String) -> JsValue {
-    static CACHE: Mutex<OnceLock<HashMap<String, ChartData<BarDataset>>>> =
-        Mutex::new(OnceLock::new());
-    {
-        let mutex_guard = CACHE.lock().unwrap();
-        let cache = mutex_guard.get_or_init(|| HashMap::new());
-        match cache.get(&mode) {
-            Some(value) => return value.serialize(&SERIALIZER).unwrap_throw(),
-            None => (),
-        }
+impl InviteSet {
+    pub fn score_data(&self) -> JsValue {
+        self.score_chart_data()
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
     }
 
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    fn per_day(date: NaiveDate) -> NaiveDate {
-        date
-    }
-    fn per_week(date: NaiveDate) -> NaiveDate {
-        date.week(Weekday::Mon).first_day()
-    }
-    fn per_month(date: NaiveDate) -> NaiveDate {
-        NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap()
-    }
+    /// `score_data` with the forecast next draw of each category, as a dashed
+    /// segment from its latest draw, see `ForecastAnalyzer::next_draws`, with
+    /// the pool inflow of `config`.
+    pub fn score_forecast_data(&self, pool_data: &PoolSet, config: &WasmRateConfig) -> JsValue {
+        let invite_data = &self[..];
+        let density = fitted_density(pool_data, invite_data);
+        let forecasts = ForecastAnalyzer::next_draws(
+            pool_data,
+            invite_data,
+            density,
+            &config.0,
+            &SimulationConfig::default(),
+        );
 
-    let fn_bar_date = if mode == "d" {
-        per_day
-    } else if mode == "w" {
-        per_week
-    } else if mode == "m" {
-        per_month
-    } else {
-        throw_str(format!("invalid mode {}", mode).as_str())
-    };
-
-    let labels: Vec<_> = invite_data
-        .iter()
-        .group_by(|invitation| fn_bar_date(invitation.date))
-        .into_iter()
-        .map(|(bar_date, _)| bar_date.to_timestamp() as f64)
-        .collect();
-    let datasets: Vec<_> = CategoryCode::values()
-        .iter()
-        .map(|category| {
-            let data: Vec<_> = invite_data
-                .iter()
-                .group_by(|invitation| fn_bar_date(invitation.date))
-                .into_iter()
-                .map(|(_, invitations)| {
-                    Some(
-                        invitations
-                            .into_iter()
-                            .map(|invitation| {
-                                if invitation.category.code == *category {
-                                    invitation.size as f64
-                                } else {
-                                    0_f64
-                                }
-                            })
-                            .reduce(|x, y| x + y)
-                            .unwrap_or(0_f64),
-                    )
-                })
-                .collect();
+        let mut chart = self.score_chart_data();
+        let n = chart.labels.len();
+        let len = n + forecasts.len();
+        for dataset in chart.datasets.iter_mut() {
+            dataset.data.resize(len, None);
+        }
+        for (k, forecast) in forecasts.iter().enumerate() {
+            chart.labels.push(forecast.date.to_timestamp() as f64);
+            chart.tooltip.title[0].push(format!(
+                "{} (forecast {:.0} - {:.0})",
+                forecast.date.format("%Y-%m-%d"),
+                forecast.score.low,
+                forecast.score.high
+            ));
 
-            BarDataset {
-                label: category.as_str(),
+            let mut data = vec![None; len];
+            if let Some(i) = invite_data
+                .iter()
+                .rposition(|invitation| invitation.category.code == forecast.code)
+            {
+                data[i] = Some(invite_data[i].score);
+            }
+            data[n + k] = Some(forecast.score.mid);
+            chart.datasets.push(LineDataset {
+                label: format!("{} (forecast)", forecast.label),
                 data,
-                background_color: category.as_color(),
-                border_color: category.as_color(),
-                stack: "0".into(),
+                background_color: forecast.code.as_color(),
+                border_color: forecast.code.as_color(),
+                border_dash: [5.0, 5.0],
+                ..Default::default()
+            });
+        }
+
+        chart.serialize(&SERIALIZER).unwrap_throw()
+    }
+
+    pub fn size_data(&self, mode: String) -> JsValue {
+        static CACHE: Mutex<OnceLock<HashMap<String, ChartData<BarDataset>>>> =
+            Mutex::new(OnceLock::new());
+        {
+            let mutex_guard = CACHE.lock().unwrap();
+            let cache = mutex_guard.get_or_init(HashMap::new);
+            if let Some(value) = cache.get(&mode) {
+                return value.serialize(&SERIALIZER).unwrap_throw();
             }
-        })
-        .collect();
-
-    let tooltip_title: Vec<_> = invite_data
-        .iter()
-        .group_by(|invitation| fn_bar_date(invitation.date))
-        .into_iter()
-        .map(|(bar_date, invitations)| {
-            let id = match invitations.minmax() {
-                itertools::MinMaxResult::NoElements => "123".into(),
-                itertools::MinMaxResult::OneElement(x) => format!("{}", x.id),
-                itertools::MinMaxResult::MinMax(x, y) => format!("{} - {}", x.id, y.id),
-            };
-
-            let date = if mode == "d" {
-                format!("{}", bar_date.format("%Y-%m-%d"))
-            } else {
-                let bar_date2 = if mode == "w" {
-                    bar_date + Days::new(7)
+        }
+
+        let invite_data = &self[..];
+        fn per_day(date: NaiveDate) -> NaiveDate {
+            date
+        }
+        fn per_week(date: NaiveDate) -> NaiveDate {
+            date.week(Weekday::Mon).first_day()
+        }
+        fn per_month(date: NaiveDate) -> NaiveDate {
+            NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap()
+        }
+
+        let fn_bar_date = if mode == "d" {
+            per_day
+        } else if mode == "w" {
+            per_week
+        } else if mode == "m" {
+            per_month
+        } else {
+            throw_str(format!("invalid mode {}", mode).as_str())
+        };
+
+        let labels: Vec<_> = invite_data
+            .iter()
+            .group_by(|invitation| fn_bar_date(invitation.date))
+            .into_iter()
+            .map(|(bar_date, _)| bar_date.to_timestamp() as f64)
+            .collect();
+        let datasets: Vec<_> = CategoryCode::values()
+            .iter()
+            .map(|category| {
+                let data: Vec<_> = invite_data
+                    .iter()
+                    .group_by(|invitation| fn_bar_date(invitation.date))
+                    .into_iter()
+                    .map(|(_, invitations)| {
+                        Some(
+                            invitations
+                                .into_iter()
+                                .map(|invitation| {
+                                    if invitation.category.code == *category {
+                                        invitation.size
+                                    } else {
+                                        0_f64
+                                    }
+                                })
+                                .reduce(|x, y| x + y)
+                                .unwrap_or(0_f64),
+                        )
+                    })
+                    .collect();
+
+                BarDataset {
+                    label: category.as_str(),
+                    data,
+                    background_color: category.as_color(),
+                    border_color: category.as_color(),
+                    stack: "0".into(),
+                }
+            })
+            .collect();
+
+        let tooltip_title: Vec<_> = invite_data
+            .iter()
+            .group_by(|invitation| fn_bar_date(invitation.date))
+            .into_iter()
+            .map(|(bar_date, invitations)| {
+                let id = match invitations.minmax() {
+                    itertools::MinMaxResult::NoElements => "123".into(),
+                    itertools::MinMaxResult::OneElement(x) => format!("{}", x.id),
+                    itertools::MinMaxResult::MinMax(x, y) => format!("{} - {}", x.id, y.id),
+                };
+
+                let date = if mode == "d" {
+                    format!("{}", bar_date.format("%Y-%m-%d"))
                 } else {
-                    bar_date + Months::new(1)
+                    let bar_date2 = if mode == "w" {
+                        bar_date + Days::new(7)
+                    } else {
+                        bar_date + Months::new(1)
+                    };
+                    format!(
+                        "{} - {}",
+                        bar_date.format("%Y-%m-%d"),
+                        bar_date2.format("%Y-%m-%d")
+                    )
                 };
-                format!(
-                    "{} - {}",
-                    bar_date.format("%Y-%m-%d"),
-                    bar_date2.format("%Y-%m-%d")
-                )
-            };
-
-            format!("{}({})", date, id)
-        })
-        .collect();
-
-    {
-        let mut mutex_guard = CACHE.lock().unwrap();
-        let cache = mutex_guard.get_mut().unwrap();
-        cache.insert(
-            mode.clone(),
-            ChartData {
-                labels,
-                datasets,
-                tooltip: Tooltip {
-                    title: vec![tooltip_title],
-                    label: Vec::new(),
+
+                format!("{}({})", date, id)
+            })
+            .collect();
+
+        {
+            let mut mutex_guard = CACHE.lock().unwrap();
+            let cache = mutex_guard.get_mut().unwrap();
+            cache.insert(
+                mode.clone(),
+                ChartData {
+                    labels,
+                    datasets,
+                    tooltip: Tooltip {
+                        title: vec![tooltip_title],
+                        label: Vec::new(),
+                    },
                 },
-            },
-        );
-        cache[&mode].serialize(&SERIALIZER).unwrap_throw()
+            );
+            cache[&mode].serialize(&SERIALIZER).unwrap_throw()
+        }
     }
-}
 
-#[wasm_bindgen]
-pub fn wasm_invite_x_min(invite_data: *const Vec<Invite>) -> JsValue {
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    invite_data
-        .first()
-        .map(|invitation| (invitation.date - Months::new(1)).to_timestamp() as f64)
-        .unwrap_or(0.0)
-        .serialize(&SERIALIZER)
-        .unwrap()
-}
+    pub fn x_min(&self) -> JsValue {
+        let invite_data = &self[..];
+        invite_data
+            .first()
+            .map(|invitation| (invitation.date - Months::new(1)).to_timestamp() as f64)
+            .unwrap_or(0.0)
+            .serialize(&SERIALIZER)
+            .unwrap()
+    }
 
-#[wasm_bindgen]
-pub fn wasm_invite_x_max(invite_data: *const Vec<Invite>) -> JsValue {
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-    invite_data
-        .last()
-        .map(|invitation| (invitation.date + Months::new(1)).to_timestamp() as f64)
-        .unwrap_or(0.0)
-        .serialize(&SERIALIZER)
-        .unwrap()
+    pub fn x_max(&self) -> JsValue {
+        let invite_data = &self[..];
+        invite_data
+            .last()
+            .map(|invitation| (invitation.date + Months::new(1)).to_timestamp() as f64)
+            .unwrap_or(0.0)
+            .serialize(&SERIALIZER)
+            .unwrap()
+    }
 }
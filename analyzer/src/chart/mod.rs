@@ -1,13 +1,19 @@
 pub mod category;
 pub mod invite;
+pub mod pathway;
+pub mod plan;
 pub mod pool;
 
 mod utils {
-    use std::{fmt::Debug, ops::Index};
+    use std::{fmt::Debug, ops::Index, sync::OnceLock};
 
     use chrono::NaiveDate;
     use serde_wasm_bindgen::Serializer;
 
+    use crate::analyze::calc::ScoreDensity;
+    use crate::analyze::density::DensityAnalyzer;
+    use crate::data::{Invite, Pool};
+
     pub trait ToTimestamp {
         fn to_timestamp(&self) -> i64;
     }
@@ -20,6 +26,13 @@ mod utils {
 
     pub static SERIALIZER: Serializer = Serializer::new().serialize_missing_as_null(true);
 
+    // fitted on the first call only, JS gets its pools and invitations from
+    // `wasm_pool_data` and `wasm_invite_data`, which load them once.
+    pub fn fitted_density(pool_data: &[Pool], invite_data: &[Invite]) -> ScoreDensity {
+        static DENSITY: OnceLock<ScoreDensity> = OnceLock::new();
+        *DENSITY.get_or_init(|| DensityAnalyzer::fit(pool_data, invite_data))
+    }
+
     #[derive(Debug, Clone, Copy, PartialEq)]
     pub struct Stacker<const N: usize, T>
     where
@@ -56,21 +69,12 @@ mod dataset {
         pub tooltip: Tooltip,
     }
 
-    #[derive(Serialize, Clone, Debug)]
+    #[derive(Serialize, Clone, Debug, Default)]
     pub struct Tooltip {
         pub title: Vec<Vec<String>>,
         pub label: Vec<Vec<String>>,
     }
 
-    impl Default for Tooltip {
-        fn default() -> Self {
-            Self {
-                title: Vec::new(),
-                label: Vec::new(),
-            }
-        }
-    }
-
     #[derive(Serialize, Clone, Debug)]
     pub struct LineDataset {
         pub label: String,
@@ -135,8 +139,8 @@ mod dataset {
     }
 
     #[derive(Serialize, Clone, Debug)]
-    pub struct Dropdown {
+    pub struct Dropdown<K: Serialize> {
         pub label: String,
-        pub key: f64,
+        pub key: K,
     }
 }
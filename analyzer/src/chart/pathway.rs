@@ -0,0 +1,186 @@
+use super::dataset::{Dropdown, PointStyle};
+use super::utils::Stacker;
+use crate::analyze::pathway::{PathwayAnalyzer, PathwaySplit};
+use crate::chart::dataset::{ChartData, LineDataset, Tooltip};
+use crate::chart::utils::{ToTimestamp, SERIALIZER};
+use crate::data::{Invite, InviteSet, PathwayCode};
+use chrono::NaiveDate;
+use itertools::Itertools;
+use serde::Serialize;
+use wasm_bindgen::{prelude::*, throw_str};
+
+#[wasm_bindgen]
+impl InviteSet {
+    pub fn pathway_years(&self) -> JsValue {
+        PathwayAnalyzer::of_years(self)
+            .keys()
+            .sorted()
+            .map(|year| Dropdown {
+                key: *year as f64,
+                label: if *year == 0 {
+                    "all".into()
+                } else {
+                    year.to_string()
+                },
+            })
+            .collect::<Vec<_>>()
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
+
+    /// `mode` is "even" to split multi-pathway draws evenly among their
+    /// pathways, "weighted:<PNP>,<CEC>,<FSW>,<FST>" to split them by the
+    /// weights of their pathways, or "draw" to keep one series per set of
+    /// eligible pathways.
+    pub fn pathway_invite_data(&self, year: f64, mode: String) -> JsValue {
+        let invite_data = PathwayAnalyzer::of_years(self)[&(year as i32)];
+
+        match mode.as_str() {
+            "draw" => pathway_draw_chart(invite_data),
+            _ => match mode.parse::<PathwaySplit>() {
+                Ok(split) => pathway_split_chart(invite_data, split),
+                Err(_) => throw_str(format!("invalid mode {}", mode).as_str()),
+            },
+        }
+    }
+}
+
+fn pathway_split_chart(invite_data: &[Invite], split: PathwaySplit) -> JsValue {
+    let (pathway_invite_labels, pathway_invites) =
+        PathwayAnalyzer::invite_per_pathway(invite_data, split);
+
+    let datasets = PathwayCode::values()
+        .iter()
+        .map(|code| {
+            let data: Vec<_> = pathway_invites
+                .iter()
+                .map(|pool| pool.normalize() * 100.0)
+                .map(|pool| Some(Stacker::<{ PathwayCode::N }, _>::new(pool).val(*code as usize)))
+                .collect();
+
+            LineDataset {
+                label: code.as_str(),
+                data,
+                background_color: code.as_color(),
+                border_color: code.as_color(),
+                fill: true,
+                point_style: PointStyle(None),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let tooltip_label: Vec<_> = PathwayCode::values()
+        .iter()
+        .map(|code| {
+            pathway_invites
+                .iter()
+                .map(|pool| {
+                    format!(
+                        "{}: {:.2}% ({})",
+                        code.as_str(),
+                        pool.normalize()[*code] * 100.0,
+                        pool[*code] as i64
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    pathway_chart(&pathway_invite_labels, datasets, tooltip_label)
+}
+
+fn pathway_draw_chart(invite_data: &[Invite]) -> JsValue {
+    let (pathway_invite_labels, pathway_invites) =
+        PathwayAnalyzer::invite_per_draw_pathway(invite_data);
+
+    let totals: Vec<f64> = (0..pathway_invite_labels.len())
+        .map(|i| pathway_invites.values().map(|values| values[i]).sum())
+        .collect();
+    let percent = |value: f64, total: f64| {
+        if total == 0.0 {
+            0.0
+        } else {
+            value / total * 100.0
+        }
+    };
+
+    // stack the sets of pathways on top of each other, in bitmask order
+    let mut stack = vec![0.0; pathway_invite_labels.len()];
+    let datasets = pathway_invites
+        .iter()
+        .map(|(pathway, values)| {
+            let data: Vec<_> = values
+                .iter()
+                .zip(totals.iter())
+                .zip(stack.iter_mut())
+                .map(|((value, total), stack)| {
+                    *stack += percent(*value, *total);
+                    Some(*stack)
+                })
+                .collect();
+            // a single pathway keeps its color, mixed draws share a neutral one
+            let color = match pathway.codes()[..] {
+                [code] => code.as_color(),
+                _ => "#BDC3C7".into(),
+            };
+
+            LineDataset {
+                label: pathway.as_str(),
+                data,
+                background_color: color.clone(),
+                border_color: color,
+                fill: true,
+                point_style: PointStyle(None),
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let tooltip_label: Vec<_> = pathway_invites
+        .iter()
+        .map(|(pathway, values)| {
+            values
+                .iter()
+                .zip(totals.iter())
+                .map(|(value, total)| {
+                    format!(
+                        "{}: {:.2}% ({})",
+                        pathway.as_str(),
+                        percent(*value, *total),
+                        *value as i64
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    pathway_chart(&pathway_invite_labels, datasets, tooltip_label)
+}
+
+fn pathway_chart(
+    pathway_invite_labels: &[NaiveDate],
+    datasets: Vec<LineDataset>,
+    tooltip_label: Vec<Vec<String>>,
+) -> JsValue {
+    let labels: Vec<_> = pathway_invite_labels
+        .iter()
+        .map(|date| date.to_timestamp() as f64)
+        .collect();
+
+    let tooltip_title: Vec<_> = pathway_invite_labels
+        .iter()
+        .map(|date| format!("{}", date.format("%Y-%m-%d")))
+        .collect();
+
+    ChartData {
+        labels,
+        datasets,
+        tooltip: Tooltip {
+            title: vec![tooltip_title],
+            label: tooltip_label,
+        },
+    }
+    .serialize(&SERIALIZER)
+    .unwrap_throw()
+}
@@ -0,0 +1,151 @@
+use crate::analyze::plan::{PlanAnalyzer, PlanScope};
+use crate::chart::dataset::{ChartData, LineDataset, Tooltip};
+use crate::chart::utils::{ToTimestamp, SERIALIZER};
+use crate::data::InviteSet;
+use chrono::{Datelike, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::Mutex;
+use wasm_bindgen::{prelude::*, throw_str};
+
+static PLAN: Mutex<Option<PlanAnalyzer>> = Mutex::new(None);
+
+fn plan_scope(pnp: bool) -> (PlanAnalyzer, PlanScope) {
+    let plan = PLAN.lock().unwrap().clone().unwrap_or_default();
+    let scope = if pnp {
+        PlanScope::Pnp
+    } else {
+        PlanScope::NonPnp
+    };
+    (plan, scope)
+}
+
+fn dataset(label: &str, color: &str, data: Vec<Option<f64>>, dashed: bool) -> LineDataset {
+    LineDataset {
+        label: label.into(),
+        data,
+        background_color: color.into(),
+        border_color: color.into(),
+        border_dash: if dashed { [5.0, 5.0] } else { [0.0, 0.0] },
+        ..Default::default()
+    }
+}
+
+// a plan config as in `plan.json`, replaces the built-in targets.
+#[wasm_bindgen]
+pub fn wasm_use_plan(text: String) -> Result<(), JsError> {
+    *PLAN.lock().unwrap() = Some(PlanAnalyzer::from_json(&text)?);
+    Ok(())
+}
+
+/// Plan against invitations per "year" or "month" `mode`, of the provincial
+/// nominee draws if `pnp`, of the others if not.
+#[wasm_bindgen]
+pub fn wasm_plan_data(invite_data: &InviteSet, mode: String, pnp: bool) -> JsValue {
+    let (plan, scope) = plan_scope(pnp);
+    let today = Utc::now().date_naive();
+    let progress = match mode.as_str() {
+        "year" => plan.yearly(invite_data, scope, today),
+        "month" => plan.monthly(invite_data, scope, today),
+        _ => throw_str(format!("invalid mode {}", mode).as_str()),
+    };
+
+    ChartData {
+        labels: progress
+            .iter()
+            .map(|x| x.date.to_timestamp() as f64)
+            .collect(),
+        datasets: vec![
+            dataset(
+                "Low Range",
+                "#F4D03F",
+                progress.iter().map(|x| Some(x.plan_min)).collect(),
+                true,
+            ),
+            dataset(
+                "High Range",
+                "#F4D03F",
+                progress.iter().map(|x| Some(x.plan_max)).collect(),
+                true,
+            ),
+            dataset(
+                "Invitation",
+                "#58D68D",
+                progress.iter().map(|x| x.actual).collect(),
+                false,
+            ),
+        ],
+        tooltip: Tooltip::default(),
+    }
+    .serialize(&SERIALIZER)
+    .unwrap_throw()
+}
+
+/// `QuotaPace` of the current year, null if it has no target.
+#[wasm_bindgen]
+pub fn wasm_plan_pace(invite_data: &InviteSet, pnp: bool) -> JsValue {
+    let (plan, scope) = plan_scope(pnp);
+    plan.pace(invite_data, scope, Utc::now().date_naive())
+        .serialize(&SERIALIZER)
+        .unwrap_throw()
+}
+
+/// Invitations of the current year added up, and where the pace so far
+/// leads them by the end of the year, against the target.
+#[wasm_bindgen]
+pub fn wasm_pace_data(invite_data: &InviteSet, pnp: bool) -> JsValue {
+    let (plan, scope) = plan_scope(pnp);
+    let today = Utc::now().date_naive();
+    let Some(pace) = plan.pace(invite_data, scope, today) else {
+        return JsValue::NULL;
+    };
+    let start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
+    let end = NaiveDate::from_ymd_opt(today.year(), 12, 31).unwrap();
+
+    let mut points = vec![(start, 0.0)];
+    points.extend(
+        PlanAnalyzer::cumulative(invite_data, scope, today.year())
+            .into_iter()
+            .filter(|(date, _)| *date <= today),
+    );
+    points.push((today, pace.invited));
+    let days = (today - start).num_days() + 1;
+    let projected = pace.invited + pace.invited / days as f64 * pace.days_left as f64;
+
+    let n = points.len();
+    let mut labels: Vec<_> = points
+        .iter()
+        .map(|(date, _)| date.to_timestamp() as f64)
+        .collect();
+    labels.push(end.to_timestamp() as f64);
+    let mut invited: Vec<_> = points.iter().map(|(_, value)| Some(*value)).collect();
+    invited.push(None);
+    let mut projection = vec![None; n + 1];
+    projection[n - 1] = Some(pace.invited);
+    projection[n] = Some(projected);
+
+    let mut chart = ChartData {
+        labels,
+        datasets: vec![
+            dataset(
+                "Low Range",
+                "#F4D03F",
+                vec![Some(pace.target.min); n + 1],
+                true,
+            ),
+            dataset(
+                "High Range",
+                "#F4D03F",
+                vec![Some(pace.target.max); n + 1],
+                true,
+            ),
+            dataset("Invitation", "#58D68D", invited, false),
+            dataset("Current Pace", "#58D68D", projection, true),
+        ],
+        tooltip: Tooltip::default(),
+    };
+    // straight segments, the pace is a linear projection
+    for dataset in chart.datasets.iter_mut() {
+        dataset.cubic_interpolation_mode = "default".into();
+    }
+    chart.serialize(&SERIALIZER).unwrap_throw()
+}
@@ -1,188 +1,322 @@
 use super::dataset::PointStyle;
 use super::utils::Stacker;
-use crate::analyze::rate::RateAnalyzer;
+use crate::analyze::probability::{CandidateProfile, ProbabilityAnalyzer};
+use crate::analyze::rank::RankAnalyzer;
+use crate::analyze::rate::{RateAnalyzer, RateConfig};
 use crate::analyze::smooth::Smoother;
 use crate::chart::dataset::{ChartData, LineDataset, Tooltip};
-use crate::chart::utils::{ToTimestamp, SERIALIZER};
-use crate::data::{Invite, Pool};
-use chrono::Days;
+use crate::chart::utils::{fitted_density, ToTimestamp, SERIALIZER};
+use crate::data::{BucketSchema, CategoryCode, InviteSet, Pool, PoolSet};
+use chrono::{DateTime, Days};
 use serde::Serialize;
 use std::iter;
 use std::ops::Mul;
-use wasm_bindgen::prelude::*;
+use wasm_bindgen::{prelude::*, throw_str};
 
 #[wasm_bindgen]
 pub fn wasm_pool_n() -> JsValue {
-    Pool::N.serialize(&SERIALIZER).unwrap_throw()
+    BucketSchema::latest()
+        .len()
+        .serialize(&SERIALIZER)
+        .unwrap_throw()
+}
+
+/// `RateConfig` handed to JS, the defaults tweaked field by field.
+#[wasm_bindgen(js_name = RateConfig)]
+#[derive(Debug, Clone, Default)]
+pub struct WasmRateConfig(pub(crate) RateConfig);
+
+#[wasm_bindgen(js_class = RateConfig)]
+impl WasmRateConfig {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// as parsed by `RemovalKernel::from_str`, e.g. "exponential:20:60"
+    #[wasm_bindgen(getter)]
+    pub fn removal(&self) -> String {
+        self.0.removal.to_string()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_removal(&mut self, removal: String) {
+        self.0.removal = removal
+            .parse()
+            .unwrap_or_else(|_| throw_str(format!("invalid removal kernel {}", removal).as_str()));
+    }
+
+    /// share of the invited candidates who accept, from 0 to 1
+    #[wasm_bindgen(getter)]
+    pub fn acceptance(&self) -> f64 {
+        self.0.acceptance
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_acceptance(&mut self, acceptance: f64) {
+        if !(0.0..=1.0).contains(&acceptance) {
+            throw_str(format!("invalid acceptance {}", acceptance).as_str());
+        }
+        self.0.acceptance = acceptance;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn projection_window(&self) -> usize {
+        self.0.projection_window
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_projection_window(&mut self, projection_window: usize) {
+        self.0.projection_window = projection_window;
+    }
+
+    /// as parsed by `Smoothing::from_str`, e.g. "loess:60"
+    #[wasm_bindgen(getter)]
+    pub fn smoothing(&self) -> String {
+        self.0.smoothing.to_string()
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_smoothing(&mut self, smoothing: String) {
+        self.0.smoothing = smoothing
+            .parse()
+            .unwrap_or_else(|_| throw_str(format!("invalid smoothing {}", smoothing).as_str()));
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn horizon_days(&self) -> u32 {
+        self.0.horizon_days
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_horizon_days(&mut self, horizon_days: u32) {
+        self.0.horizon_days = horizon_days;
+    }
 }
 
 #[wasm_bindgen]
-pub fn wasm_pool_count_data(pool_data: *const Vec<Pool>) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    let labels: Vec<_> = pool_data
-        .iter()
-        .map(|pool| pool.date.to_timestamp() as f64)
-        .collect();
-    let datasets: Vec<_> = (0..Pool::N)
-        .rev()
-        .into_iter()
-        .map(|i| {
-            let data: Vec<_> = pool_data
+impl PoolSet {
+    pub fn count_data(&self) -> JsValue {
+        let pool_data = &self[..];
+        let schema = Pool::schema_of(pool_data);
+        let labels: Vec<_> = pool_data
+            .iter()
+            .map(|pool| pool.date.to_timestamp() as f64)
+            .collect();
+        let datasets: Vec<_> = (0..schema.len())
+            .rev()
+            .map(|i| {
+                let data: Vec<_> = pool_data
+                    .iter()
+                    .map(|pool| Some(Stacker::<{ BucketSchema::MAX }, _>::new(*pool).rev(i)))
+                    .collect();
+
+                LineDataset {
+                    label: format!("{} - {}", schema.min_score(i), schema.max_score(i)),
+                    data,
+                    background_color: schema.as_color(i),
+                    border_color: schema.as_color(i),
+                    fill: true,
+                    point_style: PointStyle(None),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        ChartData {
+            labels,
+            datasets,
+            tooltip: Tooltip::default(),
+        }
+        .serialize(&SERIALIZER)
+        .unwrap_throw()
+    }
+
+    pub fn count_y_max(&self) -> JsValue {
+        let pool_date = &self[..];
+        pool_date
+            .iter()
+            .map(|pool| pool.total())
+            .max_by(|a, b| a.total_cmp(b))
+            .unwrap_or(0.0)
+            .mul(1.1)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
+
+    pub fn count_x_min(&self) -> JsValue {
+        let pool_data = &self[..];
+        pool_data
+            .first()
+            .map(|pool| pool.date.to_timestamp() as f64)
+            .unwrap_or(0.0)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
+
+    pub fn count_x_max(&self) -> JsValue {
+        let pool_data = &self[..];
+        pool_data
+            .last()
+            .map(|pool| pool.date.to_timestamp() as f64)
+            .unwrap_or(0.0)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
+
+    pub fn rate_data(
+        &self,
+        invite_data: &InviteSet,
+        consistent_only: bool,
+        config: &WasmRateConfig,
+    ) -> JsValue {
+        let config = &config.0;
+        let pool_data = &self[..];
+        let invite_data = &invite_data[..];
+        let pool_data: Vec<_> = pool_data
+            .iter()
+            .filter(|pool| !consistent_only || pool.is_consistent())
+            .copied()
+            .collect();
+
+        let schema = Pool::schema_of(&pool_data);
+        let (rate_labels, mut rate_data) = RateAnalyzer::pool_increase_rate(&pool_data, invite_data, config);
+        let projected_rate = RateAnalyzer::projected_rate(&rate_data, config);
+        Smoother::smooth(&rate_labels, &mut rate_data, config.smoothing);
+
+        let labels: Vec<_> = {
+            assert!(!rate_labels.is_empty());
+            let last_day = *rate_labels.last().unwrap();
+
+            let extra_label = last_day + Days::new(config.horizon_days as u64);
+            rate_labels
                 .iter()
-                .map(|pool| Some(Stacker::<{ Pool::N }, _>::new(*pool).rev(i)))
+                .chain([&extra_label])
+                .map(|date| date.to_timestamp() as f64)
+                .collect()
+        };
+        let actual = (0..schema.len()).rev().map(|i| {
+            let data: Vec<_> = rate_data
+                .iter()
+                .map(|rate| Some(Stacker::<{ BucketSchema::MAX }, _>::new(*rate).rev(i)))
+                .chain([None])
                 .collect();
 
             LineDataset {
-                label: format!("{} - {}", Pool::min_score(i), Pool::max_score(i)),
+                label: format!("> {}", schema.min_score(i)),
                 data,
-                background_color: Pool::as_color(i),
-                border_color: Pool::as_color(i),
-                fill: true,
+                background_color: schema.as_color(i),
+                border_color: schema.as_color(i),
                 point_style: PointStyle(None),
                 ..Default::default()
             }
-        })
-        .collect();
+        });
 
-    ChartData {
-        labels,
-        datasets,
-        tooltip: Tooltip::default(),
-    }
-    .serialize(&SERIALIZER)
-    .unwrap_throw()
-}
+        let predict = (0..schema.len()).rev().map(|i| {
+            let data: Vec<_> = iter::repeat_n(None, rate_data.len() - 1)
+                .chain([Some(
+                    Stacker::<{ BucketSchema::MAX }, _>::new(*rate_data.last().unwrap()).rev(i),
+                )])
+                .chain([Some(Stacker::<{ BucketSchema::MAX }, _>::new(projected_rate).rev(i))])
+                .collect();
 
-#[wasm_bindgen]
-pub fn wasm_pool_count_y_max(pool_data: *const Vec<Pool>) -> JsValue {
-    let pool_date = unsafe { pool_data.as_ref().unwrap_throw() };
-    pool_date
-        .iter()
-        .map(|pool| pool.total())
-        .max_by(|a, b| a.total_cmp(b))
-        .unwrap_or(0.0)
-        .mul(1.1)
-        .serialize(&SERIALIZER)
-        .unwrap_throw()
-}
+            LineDataset {
+                label: "none".into(),
+                data,
+                background_color: schema.as_color(i),
+                border_color: schema.as_color(i),
+                border_dash: [5.0, 5.0],
+                point_style: PointStyle(None),
+                ..Default::default()
+            }
+        });
 
-#[wasm_bindgen]
-pub fn wasm_pool_count_x_min(pool_data: *const Vec<Pool>) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    pool_data
-        .first()
-        .map(|pool| pool.date.to_timestamp() as f64)
-        .unwrap_or(0.0)
-        .serialize(&SERIALIZER)
-        .unwrap_throw()
-}
+        let datasets: Vec<_> = predict.chain(actual).collect();
 
-#[wasm_bindgen]
-pub fn wasm_pool_count_x_max(pool_data: *const Vec<Pool>) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    pool_data
-        .last()
-        .map(|pool| pool.date.to_timestamp() as f64)
-        .unwrap_or(0.0)
+        ChartData {
+            labels,
+            datasets,
+            tooltip: Tooltip {
+                title: Vec::new(),
+                label: (0..schema.len())
+                    .rev()
+                    .map(|i| vec![format!("{}: {:.3} per day", schema.as_str(i), projected_rate[i])])
+                    .collect(),
+            },
+        }
         .serialize(&SERIALIZER)
         .unwrap_throw()
-}
+    }
 
-#[wasm_bindgen]
-pub fn wasm_pool_rate_data(
-    pool_data: *const Vec<Pool>,
-    invite_data: *const Vec<Invite>,
-) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    let invite_data = unsafe { invite_data.as_ref().unwrap_throw() };
-
-    let (rate_labels, mut rate_data) = RateAnalyzer::pool_increase_rate(pool_data, invite_data);
-    let projected_rate = RateAnalyzer::projected_rate(&rate_data);
-    Smoother::exponential(&rate_labels, &mut rate_data, 0.03278688524);
-
-    let labels: Vec<_> = {
-        assert!(!rate_labels.is_empty());
-        let last_day = *rate_labels.last().unwrap();
-
-        let extra_label = last_day + Days::new(120);
-        rate_labels
-            .iter()
-            .chain([&extra_label])
-            .map(|date| date.to_timestamp() as f64)
-            .collect()
-    };
-    let actual = (0..Pool::N).into_iter().rev().map(|i| {
-        let data: Vec<_> = rate_data
-            .iter()
-            .map(|rate| Some(Stacker::<{ Pool::N }, _>::new(*rate).rev(i)))
-            .chain([None])
-            .collect();
+    pub fn rate_x_min(&self, config: &WasmRateConfig) -> JsValue {
+        let config = &config.0;
+        let pool_data = &self[..];
+        pool_data
+            .first()
+            .map(|pool| (pool.date + Days::new(config.removal.days() as u64)).to_timestamp() as f64)
+            .unwrap_or(0.0)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
 
-        LineDataset {
-            label: format!("> {}", Pool::min_score(i)),
-            data,
-            background_color: Pool::as_color(i),
-            border_color: Pool::as_color(i),
-            point_style: PointStyle(None),
-            ..Default::default()
-        }
-    });
-
-    let predict = (0..Pool::N).into_iter().rev().map(|i| {
-        let data: Vec<_> = iter::repeat(None)
-            .take(rate_data.len() - 1)
-            .chain([Some(
-                Stacker::<{ Pool::N }, _>::new(*rate_data.last().unwrap()).rev(i),
-            )])
-            .chain([Some(Stacker::<{ Pool::N }, _>::new(projected_rate).rev(i))])
-            .collect();
+    pub fn rate_x_max(&self, config: &WasmRateConfig) -> JsValue {
+        let config = &config.0;
+        let pool_data = &self[..];
+        pool_data
+            .last()
+            .map(|pool| (pool.date + Days::new(config.horizon_days as u64)).to_timestamp() as f64)
+            .unwrap_or(0.0)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
 
-        LineDataset {
-            label: "none".into(),
-            data,
-            background_color: Pool::as_color(i),
-            border_color: Pool::as_color(i),
-            border_dash: [5.0, 5.0],
-            point_style: PointStyle(None),
-            ..Default::default()
-        }
-    });
-
-    let datasets: Vec<_> = predict.chain(actual).collect();
-
-    ChartData {
-        labels,
-        datasets,
-        tooltip: Tooltip {
-            title: Vec::new(),
-            label: (0..Pool::N)
-                .rev()
-                .map(|i| vec![format!("{}: {:.3} per day", Pool::as_str(i), projected_rate[i])])
-                .collect(),
-        },
+    /// `ScoreRank` of `score` in the latest consistent pool on or before
+    /// `date`, a timestamp in milliseconds, with the density fitted to
+    /// `invite_data`.
+    pub fn rank_for_score(&self, invite_data: &InviteSet, score: f64, date: f64) -> JsValue {
+        let pool_data = &self[..];
+        let date = DateTime::from_timestamp_millis(date as i64)
+            .unwrap_throw()
+            .date_naive();
+        let density = fitted_density(pool_data, invite_data);
+
+        RankAnalyzer::rank_for_score(pool_data, score, date, density)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
     }
-    .serialize(&SERIALIZER)
-    .unwrap_throw()
-}
 
-#[wasm_bindgen]
-pub fn wasm_pool_rate_x_min(pool_data: *const Vec<Pool>) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    pool_data
-        .first()
-        .map(|pool| (pool.date + Days::new(RateAnalyzer::SUBMIT_DAYS as u64)).to_timestamp() as f64)
-        .unwrap_or(0.0)
-        .serialize(&SERIALIZER)
-        .unwrap_throw()
-}
+    /// `ItaProbability` of a candidate with `score`, eligible for `pathways`
+    /// such as "CEC+FSW", and for the comma separated category keys of
+    /// `categories` such as "stem,french", with the pool inflow of `config`.
+    pub fn ita_probability(
+        &self,
+        invite_data: &InviteSet,
+        score: f64,
+        pathways: String,
+        categories: String,
+        config: &WasmRateConfig,
+    ) -> JsValue {
+        let config = &config.0;
+        let pool_data = &self[..];
+        let profile = CandidateProfile {
+            score,
+            pathway: pathways
+                .parse()
+                .unwrap_or_else(|_| throw_str(format!("invalid pathways {}", pathways).as_str())),
+            categories: categories
+                .split(',')
+                .map(str::trim)
+                .filter(|key| !key.is_empty())
+                .map(|key| {
+                    CategoryCode::from_key(key)
+                        .unwrap_or_else(|| throw_str(format!("invalid category {}", key).as_str()))
+                })
+                .collect(),
+        };
+        let density = fitted_density(pool_data, invite_data);
 
-#[wasm_bindgen]
-pub fn wasm_pool_rate_x_max(pool_data: *const Vec<Pool>) -> JsValue {
-    let pool_data = unsafe { pool_data.as_ref().unwrap_throw() };
-    pool_data
-        .last()
-        .map(|pool| (pool.date + Days::new(120)).to_timestamp() as f64)
-        .unwrap_or(0.0)
-        .serialize(&SERIALIZER)
-        .unwrap_throw()
+        ProbabilityAnalyzer::ita_probability(pool_data, invite_data, &profile, density, config)
+            .serialize(&SERIALIZER)
+            .unwrap_throw()
+    }
 }
@@ -0,0 +1,212 @@
+use super::raw::EeRounds123En;
+use super::{DataSource, Invite, Pool};
+use crate::error::AnalyzerError;
+use chrono::{NaiveDate, NaiveDateTime, Timelike, Utc};
+use std::path::{Path, PathBuf};
+
+/// A local directory of IRCC documents, one file per fetched revision.
+///
+/// Files are named `<fetched>-<hash>.json`, with `fetched` in UTC, so the
+/// directory listing alone is the history of the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archive {
+    dir: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// when the document was fetched, in UTC
+    pub fetched: NaiveDateTime,
+    /// FNV-1a hash of the document as published
+    pub hash: u64,
+    pub path: PathBuf,
+}
+
+impl Snapshot {
+    const TIME_FORMAT: &'static str = "%Y%m%dT%H%M%SZ";
+
+    fn file_name(fetched: NaiveDateTime, hash: u64) -> String {
+        format!("{}-{:016x}.json", fetched.format(Self::TIME_FORMAT), hash)
+    }
+
+    fn from_path(path: PathBuf) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?;
+        let (fetched, hash) = stem.split_once('-')?;
+        Some(Self {
+            fetched: NaiveDateTime::parse_from_str(fetched, Self::TIME_FORMAT).ok()?,
+            hash: u64::from_str_radix(hash, 16).ok()?,
+            path,
+        })
+    }
+
+    pub fn source(&self) -> DataSource {
+        DataSource::File(self.path.clone())
+    }
+
+    pub async fn load(&self) -> Result<EeRounds123En, AnalyzerError> {
+        self.source().load().await
+    }
+
+    /// `Invite::parse_all` as of this snapshot.
+    pub async fn invites(&self) -> Result<Vec<Invite>, AnalyzerError> {
+        Invite::parse_all(&self.load().await?)
+    }
+
+    /// `Pool::parse_all` as of this snapshot.
+    pub async fn pools(&self) -> Result<Vec<Pool>, AnalyzerError> {
+        Pool::parse_all(&self.load().await?)
+    }
+}
+
+impl Archive {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Fetch the document from `source` and store it, see `store`.
+    pub async fn fetch(&self, source: &DataSource) -> Result<Snapshot, AnalyzerError> {
+        let bytes = source.bytes().await?;
+        self.store(&bytes, Utc::now().naive_utc())
+    }
+
+    /// Store a document fetched at `fetched`. If it is identical to a stored
+    /// snapshot, even one revised since, nothing is written and that snapshot
+    /// is returned.
+    pub fn store(&self, bytes: &[u8], fetched: NaiveDateTime) -> Result<Snapshot, AnalyzerError> {
+        // refuse to archive anything the analysis cannot read back
+        serde_json::from_slice::<EeRounds123En>(bytes)
+            .map_err(|e| AnalyzerError::Format(e.to_string()))?;
+
+        let hash = fnv1a(bytes);
+        if let Some(stored) = self
+            .snapshots()?
+            .into_iter()
+            .find(|snapshot| snapshot.hash == hash)
+        {
+            return Ok(stored);
+        }
+
+        let source_err = |e: std::io::Error| AnalyzerError::Source(e.to_string());
+        std::fs::create_dir_all(&self.dir).map_err(source_err)?;
+        let fetched = fetched.with_nanosecond(0).unwrap_or(fetched);
+        let path = self.dir.join(Snapshot::file_name(fetched, hash));
+        std::fs::write(&path, bytes).map_err(source_err)?;
+
+        Ok(Snapshot {
+            fetched,
+            hash,
+            path,
+        })
+    }
+
+    /// All snapshots, oldest first. A missing directory is an empty archive.
+    pub fn snapshots(&self) -> Result<Vec<Snapshot>, AnalyzerError> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(AnalyzerError::Source(e.to_string())),
+        };
+
+        let mut snapshots: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().extension().is_some_and(|x| x == "json"))
+            .filter_map(|entry| Snapshot::from_path(entry.path()))
+            .collect();
+        snapshots.sort_by_key(|snapshot| snapshot.fetched);
+        Ok(snapshots)
+    }
+
+    /// The latest snapshot fetched on or before `date`.
+    pub fn as_of(&self, date: NaiveDate) -> Result<Option<Snapshot>, AnalyzerError> {
+        Ok(self
+            .snapshots()?
+            .into_iter()
+            .take_while(|snapshot| snapshot.fetched.date() <= date)
+            .last())
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Archive, Invite, Pool};
+    use chrono::NaiveDate;
+
+    fn test_archive(name: &str) -> Archive {
+        let dir = std::env::temp_dir().join(format!("analyzer-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        Archive::new(dir)
+    }
+
+    #[tokio::test]
+    async fn archive() {
+        let archive = test_archive("archive");
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/ee_rounds_123_en.json"
+        );
+        let bytes0 = std::fs::read(path).unwrap();
+        let bytes1 = String::from_utf8(bytes0.clone())
+            .unwrap()
+            .replacen("\"drawSize\": \"", "\"drawSize\": \"1", 1)
+            .into_bytes();
+        let at = |day: u32| {
+            NaiveDate::from_ymd_opt(2024, 5, day)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+        };
+
+        assert!(archive.snapshots().unwrap().is_empty());
+        let s0 = archive.store(&bytes0, at(1)).unwrap();
+        assert_eq!(archive.store(&bytes0, at(2)).unwrap(), s0);
+        let s1 = archive.store(&bytes1, at(3)).unwrap();
+        assert_ne!(s0.hash, s1.hash);
+        // reverted to a revision already stored
+        assert_eq!(archive.store(&bytes0, at(4)).unwrap(), s0);
+        assert_eq!(archive.snapshots().unwrap(), vec![s0.clone(), s1.clone()]);
+
+        let date = |day: u32| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        assert_eq!(archive.as_of(date(1)).unwrap(), Some(s0.clone()));
+        assert_eq!(archive.as_of(date(2)).unwrap(), Some(s0.clone()));
+        assert_eq!(archive.as_of(date(3)).unwrap(), Some(s1.clone()));
+        assert_eq!(archive.as_of(NaiveDate::MIN).unwrap(), None);
+
+        // only the size of the revised draw differs
+        let draws = |invites: Vec<Invite>| -> Vec<_> {
+            invites
+                .iter()
+                .map(|invite| (invite.id, invite.date, invite.score, invite.size))
+                .collect()
+        };
+        let mut draws0 = draws(s0.invites().await.unwrap());
+        let draws1 = draws(s1.invites().await.unwrap());
+        assert_eq!(draws0.last().unwrap().3, 4490.0);
+        assert_eq!(draws1.last().unwrap().3, 14490.0);
+        draws0.last_mut().unwrap().3 = 14490.0;
+        assert_eq!(draws0, draws1);
+
+        let pools = |pools: Vec<Pool>| -> Vec<_> {
+            pools
+                .iter()
+                .map(|pool| (pool.date, pool.values().to_vec()))
+                .collect()
+        };
+        assert_eq!(
+            pools(s0.pools().await.unwrap()),
+            pools(s1.pools().await.unwrap())
+        );
+
+        assert!(archive.store(b"{}", at(5)).is_err());
+        std::fs::remove_dir_all(archive.dir()).unwrap();
+    }
+}
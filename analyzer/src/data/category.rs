@@ -1,123 +1,357 @@
+use crate::error::AnalyzerError;
 use regex::Regex;
+use serde::Deserialize;
+use std::sync::{LazyLock, RwLock};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-#[repr(u8)]
-pub enum CategoryCode {
-    General = 0,
-    Province,
-    Inland,
-    Oversea,
-    // occupation categories
-    Stem,
-    Health,
-    French,
-    Trade,
-    Transport,
-    Agriculture,
-    // unknown
-    Invalid,
-}
+/// A category key, handed out on first sight of the key and kept for the
+/// life of the process: installing another `CategoryRegistry` changes what
+/// the registry knows about a key, not which key a code stands for.
+///
+/// The codes are shared by every registry of the process, and there are
+/// `N` of them: once they run out, the keys seen from then on share the
+/// code of "Other".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CategoryCode(u8);
 
 impl CategoryCode {
-    pub const N: usize = 10;
-
-    pub fn values() -> &'static [CategoryCode] {
-        return &[
-            Self::General,
-            Self::Province,
-            Self::Inland,
-            Self::Oversea,
-            Self::Stem,
-            Self::Health,
-            Self::French,
-            Self::Trade,
-            Self::Transport,
-            Self::Agriculture,
-        ];
+    /// capacity of the codes, including those created for unknown draw
+    /// names
+    pub const N: usize = 32;
+
+    // handed out first, the analysis splits general draws into PNP and
+    // non-PNP invitations
+    pub const GENERAL: Self = Self(0);
+    pub const PROVINCE: Self = Self(1);
+
+    /// the categories of the installed registry, then those created for
+    /// unknown draw names
+    pub fn values() -> Vec<CategoryCode> {
+        let keys: Vec<_> = CategoryRegistry::with(|registry| {
+            registry
+                .entries
+                .iter()
+                .map(|entry| entry.key.clone())
+                .collect()
+        });
+        let codes = CODES.read().unwrap();
+        keys.iter()
+            .filter_map(|key| codes.find(key))
+            .chain(
+                (0..codes.entries.len())
+                    .filter(|i| codes.entries[*i].other)
+                    .map(|i| Self(i as u8)),
+            )
+            .collect()
+    }
+
+    /// the category with `key` in the installed registry
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::values().into_iter().find(|code| code.key() == key)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn key(&self) -> String {
+        CODES.read().unwrap().entries[self.index()].key.clone()
     }
 
     pub fn as_str(&self) -> String {
-        match self {
-            Self::General => "General".into(),
-            Self::Province => "PNP".into(),
-            Self::Inland => "CEC".into(),
-            Self::Oversea => "FSW".into(),
-            Self::Stem => "STEM".into(),
-            Self::Health => "Health".into(),
-            Self::French => "French".into(),
-            Self::Trade => "Trade".into(),
-            Self::Transport => "Transport".into(),
-            Self::Agriculture => "Agriculture".into(),
-            Self::Invalid => "Unknown".into(),
-        }
+        let label = CODES.read().unwrap().entries[self.index()].label.clone();
+        self.with_entry(|entry| entry.map_or(label, |entry| entry.label.clone()))
     }
 
     pub fn as_color(&self) -> String {
-        match self {
-            Self::General => "#ECF0F1".into(),
-            Self::Province => "#9B59B6".into(),
-            Self::Inland => "#E74C3C".into(),
-            Self::Oversea => "#C0392B".into(),
-            Self::Stem => "#3498DB".into(),
-            Self::Health => "#16A085".into(),
-            Self::French => "#D35400".into(),
-            Self::Trade => "#7F8C8D".into(),
-            Self::Transport => "#F39C12".into(),
-            Self::Agriculture => "#2ECC71".into(),
-            Self::Invalid => "#000000".into(),
+        let key = self.key();
+        CategoryRegistry::with(|registry| match registry.find(&key) {
+            Some(entry) => entry.color.clone(),
+            None => registry.other_color.clone(),
+        })
+    }
+
+    pub fn group(&self) -> String {
+        self.with_entry(|entry| entry.map_or("other".into(), |entry| entry.group.clone()))
+    }
+
+    /// whether the installed registry does not know the category, e.g. one
+    /// created for an unknown draw name
+    pub fn is_other(&self) -> bool {
+        self.with_entry(|entry| entry.is_none())
+    }
+
+    fn with_entry<R>(&self, f: impl FnOnce(Option<&CategoryEntry>) -> R) -> R {
+        let key = self.key();
+        CategoryRegistry::with(|registry| f(registry.find(&key)))
+    }
+}
+
+// the key of every code handed out, in the order of first sight
+#[derive(Debug, Default)]
+struct CodeTable {
+    entries: Vec<CodeEntry>,
+}
+
+#[derive(Debug)]
+struct CodeEntry {
+    key: String,
+    /// shown when the installed registry does not know `key`
+    label: String,
+    /// created for an unknown draw name
+    other: bool,
+}
+
+impl CodeTable {
+    fn find(&self, key: &str) -> Option<CategoryCode> {
+        self.entries
+            .iter()
+            .position(|entry| entry.key == key)
+            .map(|i| CategoryCode(i as u8))
+    }
+
+    fn intern(&mut self, key: &str, label: &str, other: bool) -> CategoryCode {
+        if let Some(code) = self.find(key) {
+            return code;
         }
+        if self.entries.len() >= CategoryCode::N - 1 && key != "other" {
+            // out of codes, the remaining keys share the last one
+            return self.intern("other", "Other", true);
+        }
+        self.entries.push(CodeEntry {
+            key: key.into(),
+            label: label.into(),
+            other,
+        });
+        CategoryCode((self.entries.len() - 1) as u8)
+    }
+}
+
+static CODES: LazyLock<RwLock<CodeTable>> = LazyLock::new(|| {
+    let mut codes = CodeTable::default();
+    let registry = CategoryRegistry::default();
+    let fixed = ["general", "pnp"]
+        .iter()
+        .filter_map(|key| registry.find(key));
+    for entry in fixed.chain(&registry.entries) {
+        codes.intern(&entry.key, &entry.label, false);
+    }
+    RwLock::new(codes)
+});
+
+// the code of `key`, only locks the codes for writing on first sight
+fn intern(key: &str, label: &str, other: bool) -> CategoryCode {
+    let code = CODES.read().unwrap().find(key);
+    code.unwrap_or_else(|| CODES.write().unwrap().intern(key, label, other))
+}
+
+// the round suffix of a draw name, e.g. " (2024-1)"
+static ROUND: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s*\((20[0-9][0-9])-([0-9]+)\)").unwrap());
+
+#[derive(Debug, Clone)]
+struct CategoryEntry {
+    key: String,
+    label: String,
+    color: String,
+    group: String,
+    patterns: Vec<Regex>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CategoryConfig {
+    other_color: String,
+    categories: Vec<CategoryEntryConfig>,
+}
+
+#[derive(Deserialize)]
+struct CategoryEntryConfig {
+    key: String,
+    label: String,
+    color: String,
+    group: String,
+    patterns: Vec<String>,
+}
+
+/// Maps `drawName` to categories, loaded from a config such as `categories.json`.
+///
+/// A draw name belongs to the first category, in the order of the config,
+/// with a pattern matching it. Draw names matching no pattern get a category
+/// "Other: <name>" of their own, created on first sight, so new IRCC
+/// categories show up without a release.
+#[derive(Debug, Clone)]
+pub struct CategoryRegistry {
+    entries: Vec<CategoryEntry>,
+    other_color: String,
+}
+
+static REGISTRY: LazyLock<RwLock<CategoryRegistry>> =
+    LazyLock::new(|| RwLock::new(CategoryRegistry::default()));
+
+impl Default for CategoryRegistry {
+    fn default() -> Self {
+        Self::from_json(include_str!("../../categories.json")).unwrap()
+    }
+}
+
+impl CategoryRegistry {
+    pub fn from_json(text: &str) -> Result<Self, AnalyzerError> {
+        fn format_err(e: impl std::fmt::Display) -> AnalyzerError {
+            AnalyzerError::Format(format!("category config: {}", e))
+        }
+
+        let config: CategoryConfig = serde_json::from_str(text).map_err(format_err)?;
+        let entries = config
+            .categories
+            .into_iter()
+            .map(|entry| {
+                Ok(CategoryEntry {
+                    key: entry.key,
+                    label: entry.label,
+                    color: entry.color,
+                    group: entry.group,
+                    patterns: entry
+                        .patterns
+                        .iter()
+                        .map(|pattern| Regex::new(pattern))
+                        .collect::<Result<_, _>>()
+                        .map_err(format_err)?,
+                })
+            })
+            .collect::<Result<Vec<_>, AnalyzerError>>()?;
+
+        // the analysis depends on these, they keep their place in the order
+        for key in ["general", "pnp"] {
+            if entries.iter().all(|entry| entry.key != key) {
+                return Err(format_err(format!("missing category {:?}", key)));
+            }
+        }
+        if entries.iter().any(|entry| entry.patterns.is_empty()) {
+            return Err(format_err("every category needs a pattern"));
+        }
+        // keep a code for the catch-all "Other"
+        if entries.len() >= CategoryCode::N {
+            return Err(format_err(format!(
+                "more than {} categories",
+                CategoryCode::N - 1
+            )));
+        }
+
+        Ok(Self {
+            entries,
+            other_color: config.other_color,
+        })
+    }
+
+    /// Use this registry for every `Category` parsed from now on. The codes
+    /// already handed out keep their keys, those of the new categories are
+    /// handed out here.
+    pub fn install(self) {
+        let mut codes = CODES.write().unwrap();
+        for entry in &self.entries {
+            codes.intern(&entry.key, &entry.label, false);
+        }
+        drop(codes);
+        *REGISTRY.write().unwrap() = self;
+    }
+
+    fn with<R>(f: impl FnOnce(&Self) -> R) -> R {
+        f(&REGISTRY.read().unwrap())
+    }
+
+    fn find(&self, key: &str) -> Option<&CategoryEntry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+
+    /// The code of the first category `name` matches, or of the category
+    /// "Other: <name>", one per unknown name whatever the round. A key seen
+    /// for the first time takes one of the codes shared by the process, see
+    /// `CategoryCode`.
+    pub fn resolve(&self, name: &str) -> CategoryCode {
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|entry| entry.patterns.iter().any(|re| re.is_match(name)))
+        {
+            return intern(&entry.key, &entry.label, false);
+        }
+
+        let name = ROUND.replace_all(name, "").trim().to_string();
+        let key = format!("other:{}", name.to_lowercase());
+        let label = format!("Other: {}", name);
+        intern(&key, &label, true)
+    }
+
+    /// `Category::parse` with this registry instead of the installed one.
+    pub fn category(&self, raw_data: &str) -> Category {
+        Category {
+            code: self.resolve(raw_data),
+            round: Category::parse_round(raw_data),
+        }
+    }
+}
+
+/// The eligibility round of a category draw, e.g. "2023-1" in
+/// "Healthcare occupations (2023-1)". IRCC may redefine the eligible
+/// occupations between rounds of the same year.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CategoryRound {
+    pub year: i32,
+    pub round: i32,
+}
+
+impl std::fmt::Display for CategoryRound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.year, self.round)
+    }
+}
+
+impl std::str::FromStr for CategoryRound {
+    type Err = AnalyzerError;
+
+    fn from_str(x: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let (year, round) = x.split_once('-')?;
+            Some(Self {
+                year: year.parse().ok()?,
+                round: round.parse().ok()?,
+            })
+        };
+        parse().ok_or(AnalyzerError::Format(format!(
+            "invalid category round {:?}",
+            x
+        )))
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Category {
     pub code: CategoryCode,
-    pub year: Option<i32>,
+    pub round: Option<CategoryRound>,
 }
 
 impl Category {
-    fn parse_year(raw_data: &str) -> Option<i32> {
-        let re = Regex::new(r"\(20[0-9][0-9]-[0-9]\)").unwrap();
-        
-        re.find(raw_data).map(|m| {
-            format!("{}", &m.as_str()[1..5]).parse().unwrap()
+    // `None` as well for a round out of range, e.g. "(2024-99999999999)"
+    fn parse_round(raw_data: &str) -> Option<CategoryRound> {
+        let m = ROUND.captures(raw_data)?;
+        Some(CategoryRound {
+            year: m[1].parse().ok()?,
+            round: m[2].parse().ok()?,
         })
     }
 
+    /// the category of `drawName` in the installed registry
     pub fn parse(raw_data: &str) -> Self {
-        let code = if raw_data == "No Program Specified" || raw_data == "General" {
-            CategoryCode::General
-        } else if raw_data == "Provincial Nominee Program" {
-            CategoryCode::Province
-        } else if raw_data == "Canadian Experience Class" {
-            CategoryCode::Inland
-        } else if raw_data == "Federal Skilled Worker" {
-            CategoryCode::Oversea
-        } else if raw_data == "Federal Skilled Trades" || raw_data.starts_with("Trade occupations")
-        {
-            CategoryCode::Trade
-        } else if raw_data.starts_with("STEM occupations") {
-            CategoryCode::Stem
-        } else if raw_data.starts_with("Healthcare occupations") {
-            CategoryCode::Health
-        } else if raw_data.starts_with("French language proficiency") {
-            CategoryCode::French
-        } else if raw_data.starts_with("Transport occupations") {
-            CategoryCode::Transport
-        } else if raw_data.starts_with("Agriculture and agri-food occupations") {
-            CategoryCode::Agriculture
-        } else {
-            CategoryCode::Invalid
-        };
+        CategoryRegistry::with(|registry| registry.category(raw_data))
+    }
 
-        Category {
-            code,
-            year: Category::parse_year(raw_data),
-        }
+    pub fn year(&self) -> Option<i32> {
+        self.round.map(|round| round.year)
     }
 
-    pub fn is_valid(&self) -> bool {
-        self.code != CategoryCode::Invalid
+    /// whether `drawName` matched a category of the registry
+    pub fn is_known(&self) -> bool {
+        !self.code.is_other()
     }
 
     pub fn as_str(&self) -> String {
@@ -128,3 +362,123 @@ impl Category {
         self.code.as_color()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Category, CategoryCode, CategoryRegistry, CategoryRound, CodeTable};
+    use crate::error::AnalyzerError;
+
+    #[test]
+    fn registry() {
+        let registry = CategoryRegistry::default();
+        let code = |name: &str| registry.resolve(name);
+
+        assert_eq!(code("No Program Specified"), CategoryCode::GENERAL);
+        assert_eq!(code("Provincial Nominee Program"), CategoryCode::PROVINCE);
+        assert_eq!(code("Healthcare occupations (2024-1)").index(), 5);
+        let other = code("Education occupations (2024-1)");
+        assert_eq!(code("Education occupations (2025-1)"), other);
+        assert_ne!(code("Physicians (2025-1)"), other);
+        assert_eq!(other.key(), "other:education occupations");
+        assert_eq!(other.as_str(), "Other: Education occupations");
+        assert_eq!(other.group(), "other");
+        assert!(other.is_other());
+        assert!(CategoryCode::values().contains(&other));
+        assert_eq!(CategoryCode::from_key("pnp"), Some(CategoryCode::PROVINCE));
+        assert_eq!(CategoryCode::from_key("unknown"), None);
+
+        // codes are handed out in the order of first sight, up to the last
+        // one shared by the remaining keys
+        let mut codes = CodeTable::default();
+        for i in 0..CategoryCode::N - 1 {
+            assert_eq!(codes.intern(&format!("round {}", i), "", true).index(), i);
+        }
+        let last = codes.intern("round 99", "", true);
+        assert_eq!(last.index(), CategoryCode::N - 1);
+        assert_eq!(codes.entries[last.index()].key, "other");
+        assert_eq!(codes.intern("round 100", "", true), last);
+        assert_eq!(codes.intern("round 0", "", true).index(), 0);
+    }
+
+    #[test]
+    fn registry_config() {
+        let config = r##"{
+            "otherColor": "#000000",
+            "categories": [
+                {"key": "pnp", "label": "PNP", "color": "#9B59B6", "group": "program", "patterns": ["^Provincial"]},
+                {"key": "education", "label": "Education", "color": "#FFFFFF", "group": "occupation", "patterns": ["^Education"]},
+                {"key": "general", "label": "General", "color": "#ECF0F1", "group": "program", "patterns": ["^General$"]}
+            ]
+        }"##;
+        let registry = CategoryRegistry::from_json(config).unwrap();
+
+        assert_eq!(registry.resolve("General"), CategoryCode::GENERAL);
+        assert_eq!(
+            registry.resolve("Provincial Nominee Program"),
+            CategoryCode::PROVINCE
+        );
+
+        // a code stands for its key whatever the registry, the installed one
+        // does not know this one
+        let education = registry.category("Education occupations (2024-1)");
+        assert_eq!(education.code.key(), "education");
+        assert_eq!(education.round, "2024-1".parse().ok());
+        assert_ne!(
+            education.code,
+            Category::parse("Education occupations (2024-1)").code
+        );
+        assert!(education.code.is_other());
+        assert_eq!(education.code.as_str(), "Education");
+
+        // the first matching category in the order of the config wins
+        let first = config.replace(
+            "\"categories\": [",
+            r##""categories": [
+                {"key": "all", "label": "All", "color": "#000000", "group": "program", "patterns": ["^Gen"]},"##,
+        );
+        let registry = CategoryRegistry::from_json(&first).unwrap();
+        assert_eq!(registry.resolve("General").key(), "all");
+
+        let missing = config.replace("\"general\"", "\"all\"");
+        assert!(matches!(
+            CategoryRegistry::from_json(&missing),
+            Err(AnalyzerError::Format(_))
+        ));
+        let invalid = config.replace("^Education", "(Education");
+        assert!(matches!(
+            CategoryRegistry::from_json(&invalid),
+            Err(AnalyzerError::Format(_))
+        ));
+    }
+
+    #[test]
+    fn category_round() {
+        let round = |year, round| Some(CategoryRound { year, round });
+
+        assert_eq!(
+            Category::parse("Healthcare occupations (2023-1)").round,
+            round(2023, 1)
+        );
+        assert_eq!(
+            Category::parse("STEM occupations (2024-12)").round,
+            round(2024, 12)
+        );
+        assert_eq!(Category::parse("Canadian Experience Class").round, None);
+        assert_eq!(
+            Category::parse("STEM occupations (2024-99999999999)").round,
+            None
+        );
+        assert_eq!(
+            Category::parse("Trade occupations (2024-2)").year(),
+            Some(2024)
+        );
+
+        assert_eq!("2024-2".parse::<CategoryRound>().ok(), round(2024, 2));
+        assert_eq!(round(2024, 2).unwrap().to_string(), "2024-2");
+        assert!(matches!(
+            "2024".parse::<CategoryRound>(),
+            Err(AnalyzerError::Format(_))
+        ));
+        assert!(round(2023, 2) < round(2024, 1));
+    }
+}
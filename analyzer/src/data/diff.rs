@@ -0,0 +1,266 @@
+use super::raw::EeRounds123En;
+use super::{Category, Invite, Pool};
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum SnapshotChange {
+    /// a draw only in the new snapshot
+    AddedDraw { draw: String, date: String },
+    /// a draw only in the old snapshot
+    RemovedDraw { draw: String, date: String },
+    /// `size`, `score` or `category` of a draw was edited
+    RevisedDraw {
+        draw: String,
+        field: String,
+        old: String,
+        new: String,
+    },
+    /// a pool distribution only in the new snapshot
+    AddedPool { date: String },
+    /// a pool distribution only in the old snapshot
+    RemovedPool { date: String },
+    /// the count of a score range was edited
+    RevisedPool {
+        date: String,
+        bucket: String,
+        old: f64,
+        new: f64,
+    },
+}
+
+impl std::fmt::Display for SnapshotChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::AddedDraw { draw, date } => write!(f, "draw {}: added ({})", draw, date),
+            Self::RemovedDraw { draw, date } => write!(f, "draw {}: removed ({})", draw, date),
+            Self::RevisedDraw {
+                draw,
+                field,
+                old,
+                new,
+            } => write!(f, "draw {}: {} {} -> {}", draw, field, old, new),
+            Self::AddedPool { date } => write!(f, "pool on {}: added", date),
+            Self::RemovedPool { date } => write!(f, "pool on {}: removed", date),
+            Self::RevisedPool {
+                date,
+                bucket,
+                old,
+                new,
+            } => write!(f, "pool on {}: {} {} -> {}", date, bucket, old, new),
+        }
+    }
+}
+
+/// What changed between two revisions of the IRCC document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SnapshotDiff {
+    pub changes: Vec<SnapshotChange>,
+}
+
+impl std::fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.changes
+            .iter()
+            .try_for_each(|change| writeln!(f, "{}", change))
+    }
+}
+
+impl SnapshotDiff {
+    /// Compare two documents, each parsed with `parse_all_lenient`.
+    pub fn diff(old: &EeRounds123En, new: &EeRounds123En) -> Self {
+        let (old_invites, _) = Invite::parse_all_lenient(old);
+        let (old_pools, _) = Pool::parse_all_lenient(old);
+        let (new_invites, _) = Invite::parse_all_lenient(new);
+        let (new_pools, _) = Pool::parse_all_lenient(new);
+        Self::new(&old_invites, &old_pools, &new_invites, &new_pools)
+    }
+
+    pub fn new(
+        old_invites: &[Invite],
+        old_pools: &[Pool],
+        new_invites: &[Invite],
+        new_pools: &[Pool],
+    ) -> Self {
+        let mut diff = Self::default();
+        diff.diff_invites(old_invites, new_invites);
+        diff.diff_pools(old_pools, new_pools);
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn diff_invites(&mut self, old: &[Invite], new: &[Invite]) {
+        let mut invites: BTreeMap<_, (Option<&Invite>, Option<&Invite>)> = BTreeMap::new();
+        old.iter()
+            .for_each(|invite| invites.entry(invite.id).or_default().0 = Some(invite));
+        new.iter()
+            .for_each(|invite| invites.entry(invite.id).or_default().1 = Some(invite));
+
+        for (id, pair) in invites {
+            let draw = id.to_string();
+            match pair {
+                (None, Some(new)) => self.changes.push(SnapshotChange::AddedDraw {
+                    draw,
+                    date: format_date(new.date),
+                }),
+                (Some(old), None) => self.changes.push(SnapshotChange::RemovedDraw {
+                    draw,
+                    date: format_date(old.date),
+                }),
+                (Some(old), Some(new)) => {
+                    let fields = [
+                        ("size", old.size.to_string(), new.size.to_string()),
+                        ("score", old.score.to_string(), new.score.to_string()),
+                        (
+                            "category",
+                            format_category(&old.category),
+                            format_category(&new.category),
+                        ),
+                    ];
+                    for (field, old, new) in fields {
+                        if old != new {
+                            self.changes.push(SnapshotChange::RevisedDraw {
+                                draw: draw.clone(),
+                                field: field.into(),
+                                old,
+                                new,
+                            });
+                        }
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+
+    fn diff_pools(&mut self, old: &[Pool], new: &[Pool]) {
+        let mut pools: BTreeMap<_, (Option<&Pool>, Option<&Pool>)> = BTreeMap::new();
+        old.iter()
+            .for_each(|pool| pools.entry(pool.date).or_default().0 = Some(pool));
+        new.iter()
+            .for_each(|pool| pools.entry(pool.date).or_default().1 = Some(pool));
+
+        for (date, pair) in pools {
+            let date = format_date(date);
+            match pair {
+                (None, Some(_)) => self.changes.push(SnapshotChange::AddedPool { date }),
+                (Some(_), None) => self.changes.push(SnapshotChange::RemovedPool { date }),
+                (Some(old), Some(new)) => {
+                    // each document is put on the schema of its latest pool,
+                    // compare on the new one
+                    let old = old.to_schema(new.schema);
+                    for i in (0..new.len()).rev().filter(|i| old[*i] != new[*i]) {
+                        self.changes.push(SnapshotChange::RevisedPool {
+                            date: date.clone(),
+                            bucket: new.schema.as_str(i),
+                            old: old[i],
+                            new: new[i],
+                        });
+                    }
+                }
+                (None, None) => unreachable!(),
+            }
+        }
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn format_category(category: &Category) -> String {
+    match category.round {
+        Some(round) => format!("{} ({})", category.as_str(), round),
+        None => category.as_str(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{test_raw_data, BucketSchema, Pool};
+    use super::{SnapshotChange, SnapshotDiff};
+    use chrono::NaiveDate;
+
+    #[tokio::test]
+    async fn diff() {
+        let new = test_raw_data().await;
+        assert!(SnapshotDiff::diff(new, new).is_empty());
+
+        let mut old = new.clone();
+        old.rounds.remove(0);
+        old.rounds[1].draw_size = "1,234".into();
+        old.rounds[1].draw_name = "Federal Skilled Worker".into();
+        old.rounds[2].dd17 = "0".into();
+        let diff = SnapshotDiff::diff(&old, new);
+
+        let date = |x: &str| {
+            NaiveDate::parse_from_str(x, "%B %d, %Y")
+                .unwrap()
+                .to_string()
+        };
+        let round = |i: usize| &new.rounds[i];
+        assert_eq!(
+            diff.changes,
+            vec![
+                SnapshotChange::RevisedDraw {
+                    draw: round(2).draw_number.clone(),
+                    field: "size".into(),
+                    old: "1234".into(),
+                    new: round(2).draw_size.replace(',', ""),
+                },
+                SnapshotChange::RevisedDraw {
+                    draw: round(2).draw_number.clone(),
+                    field: "category".into(),
+                    old: "FSW".into(),
+                    new: "French (2024-1)".into(),
+                },
+                SnapshotChange::AddedDraw {
+                    draw: round(0).draw_number.clone(),
+                    date: date(&round(0).draw_date_full),
+                },
+                SnapshotChange::RevisedPool {
+                    date: date(&round(3).draw_distribution_as_on),
+                    bucket: "0 - 300".into(),
+                    old: 0.0,
+                    new: round(3).dd17.replace(',', "").parse().unwrap(),
+                },
+                SnapshotChange::AddedPool {
+                    date: date(&round(0).draw_distribution_as_on),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.to_string().lines().nth(1),
+            Some(
+                format!(
+                    "draw {}: category FSW -> French (2024-1)",
+                    round(2).draw_number
+                )
+                .as_str()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn diff_schema() {
+        let pool = Pool::parse(&test_raw_data().await.rounds[0]).unwrap();
+        let coarse = pool.to_schema(&BucketSchema::COARSE);
+
+        // the same counts, spread evenly over the ranges of the new schema
+        let even = coarse.to_schema(pool.schema);
+        assert!(SnapshotDiff::new(&[], &[coarse], &[], &[even]).is_empty());
+
+        let diff = SnapshotDiff::new(&[], &[coarse], &[], &[pool]);
+        assert!(!diff.is_empty());
+        // only the ranges between 401 and 500 were coarse
+        assert!(diff.changes.iter().all(|change| matches!(
+            change,
+            SnapshotChange::RevisedPool { bucket, .. } if bucket.starts_with('4')
+        )));
+    }
+}
@@ -1,7 +1,8 @@
 use super::raw::{EeRounds123En, RawData};
-use super::utils::{parse_date, parse_i32};
+use super::utils::{parse_date, parse_date_time, parse_i32};
 use super::{Category, Pathway};
-use chrono::NaiveDate;
+use crate::error::AnalyzerError;
+use chrono::{NaiveDate, NaiveDateTime};
 use itertools::Itertools;
 use std::cmp::Ordering;
 
@@ -19,11 +20,19 @@ impl std::fmt::Display for InviteId {
 }
 
 impl InviteId {
-    pub fn parse(x: &str) -> Self {
+    pub fn parse(x: &str) -> Result<Self, AnalyzerError> {
         match x {
-            "91a" => Self(91, 0),
-            "91b" => Self(91, 1),
-            _ => Self(parse_i32(x), 0),
+            "91a" => Ok(Self(91, 0)),
+            "91b" => Ok(Self(91, 1)),
+            _ => x
+                .replace(',', "")
+                .parse()
+                .map(|id| Self(id, 0))
+                .map_err(|_| AnalyzerError::Field {
+                    draw: x.into(),
+                    field: "drawNumber",
+                    value: x.into(),
+                }),
         }
     }
 }
@@ -38,10 +47,15 @@ impl From<InviteId> for i32 {
 pub struct Invite {
     pub id: InviteId,
     pub date: NaiveDate,
+    /// when the round was run, in UTC
+    pub date_time: Option<NaiveDateTime>,
     pub category: Category,
     pub pathway: Pathway,
     pub size: f64,
     pub score: f64,
+    /// candidates at exactly `score` are invited only if their profile was
+    /// submitted before this time, in UTC
+    pub tie_break: Option<NaiveDateTime>,
 }
 
 impl PartialEq for Invite {
@@ -65,64 +79,153 @@ impl Ord for Invite {
 }
 
 impl Invite {
-    pub fn parse(raw_data: &RawData) -> Self {
-        Self {
-            id: InviteId::parse(&raw_data.draw_number),
-            date: parse_date(&raw_data.draw_date_full),
+    pub fn parse(raw_data: &RawData) -> Result<Self, AnalyzerError> {
+        Ok(Self {
+            id: InviteId::parse(&raw_data.draw_number)?,
+            date: parse_date(raw_data, "drawDateFull", &raw_data.draw_date_full)?,
+            date_time: parse_date_time(raw_data, "drawDateTime", &raw_data.draw_date_time)?,
             category: Category::parse(&raw_data.draw_name),
             pathway: Pathway::parse(&raw_data.draw_text2),
-            size: parse_i32(&raw_data.draw_size) as f64,
-            score: parse_i32(&raw_data.draw_crs) as f64,
-        }
+            size: parse_i32(raw_data, "drawSize", &raw_data.draw_size)? as f64,
+            score: parse_i32(raw_data, "drawCRS", &raw_data.draw_crs)? as f64,
+            tie_break: parse_date_time(raw_data, "drawCutOff", &raw_data.draw_cutoff)?,
+        })
     }
 
-    // ensure sorted
-    pub fn parse_all(raw_data: &EeRounds123En) -> Vec<Self> {
-        raw_data
+    // ensure sorted, fails on the first malformed round
+    pub fn parse_all(raw_data: &EeRounds123En) -> Result<Vec<Self>, AnalyzerError> {
+        let invites = raw_data
             .rounds
             .iter()
-            .map(|round| Self::parse(round))
+            .map(Self::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::finalize(invites))
+    }
+
+    // ensure sorted, skips malformed rounds and reports them
+    pub fn parse_all_lenient(raw_data: &EeRounds123En) -> (Vec<Self>, Vec<AnalyzerError>) {
+        let (invites, errors): (Vec<_>, Vec<_>) =
+            raw_data.rounds.iter().map(Self::parse).partition_result();
+        (Self::finalize(invites), errors)
+    }
+
+    fn finalize(invites: Vec<Self>) -> Vec<Self> {
+        invites
+            .into_iter()
             .filter(|invitation| invitation.is_valid())
             .sorted()
             .collect::<Vec<_>>()
     }
 
     pub fn is_valid(&self) -> bool {
-        self.category.is_valid() && self.pathway.is_valid()
+        self.pathway.is_valid()
+    }
+
+    /// Whether a profile with `score`, submitted at `submitted` (UTC), would
+    /// have been invited. `None` if the score ties and the round has no
+    /// tie-breaking rule.
+    pub fn qualifies(&self, score: f64, submitted: NaiveDateTime) -> Option<bool> {
+        match score.partial_cmp(&self.score)? {
+            Ordering::Greater => Some(true),
+            Ordering::Less => Some(false),
+            Ordering::Equal => self.tie_break.map(|tie_break| submitted <= tie_break),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::raw::raw_data;
+    use super::super::test_raw_data;
     use super::{Invite, InviteId};
+    use crate::error::AnalyzerError;
+    use chrono::NaiveDateTime;
     use itertools::Itertools;
 
     #[tokio::test]
     async fn parse_data() {
-        let x = raw_data().await;
-        let i = Invite::parse_all(&x);
+        let x = test_raw_data().await;
+        let i = Invite::parse_all(x).unwrap();
 
         assert!(i.iter().all(|x| x.is_valid()));
     }
 
+    #[tokio::test]
+    async fn parse_error() {
+        let mut x = test_raw_data().await.clone();
+        x.rounds[3].draw_crs = "".into();
+        let draw = x.rounds[3].draw_number.clone();
+
+        let expect = AnalyzerError::Field {
+            draw,
+            field: "drawCRS",
+            value: "".into(),
+        };
+        assert_eq!(Invite::parse_all(&x), Err(expect.clone()));
+
+        let (i, errors) = Invite::parse_all_lenient(&x);
+        assert_eq!(errors, vec![expect]);
+        assert_eq!(
+            i.len(),
+            Invite::parse_all(test_raw_data().await).unwrap().len() - 1
+        );
+    }
+
+    #[tokio::test]
+    async fn tie_break() {
+        let mut x = test_raw_data().await.clone();
+        x.rounds[0].draw_cutoff = "January 23, 2024 at 14:11:36 UTC".into();
+        x.rounds[0].draw_date_time = "January 31, 2024 at 15:07:40 UTC".into();
+        x.rounds[1].draw_cutoff = "".into();
+        let at = |x: &str| NaiveDateTime::parse_from_str(x, "%Y-%m-%d %H:%M:%S").unwrap();
+
+        let i0 = Invite::parse(&x.rounds[0]).unwrap();
+        let i1 = Invite::parse(&x.rounds[1]).unwrap();
+        assert_eq!(i0.tie_break, Some(at("2024-01-23 14:11:36")));
+        assert_eq!(i0.date_time, Some(at("2024-01-31 15:07:40")));
+        assert_eq!(i1.tie_break, None);
+
+        let (score, submitted) = (i0.score, at("2024-01-23 14:11:36"));
+        assert_eq!(
+            i0.qualifies(score + 1.0, at("2024-01-30 00:00:00")),
+            Some(true)
+        );
+        assert_eq!(
+            i0.qualifies(score - 1.0, at("2023-01-01 00:00:00")),
+            Some(false)
+        );
+        assert_eq!(i0.qualifies(score, submitted), Some(true));
+        assert_eq!(i0.qualifies(score, at("2024-01-23 14:11:37")), Some(false));
+        assert_eq!(i1.qualifies(i1.score, submitted), None);
+
+        x.rounds[2].draw_cutoff = "January 23, 2024".into();
+        assert_eq!(
+            Invite::parse(&x.rounds[2]),
+            Err(AnalyzerError::Field {
+                draw: x.rounds[2].draw_number.clone(),
+                field: "drawCutOff",
+                value: "January 23, 2024".into(),
+            })
+        );
+    }
+
     #[tokio::test]
     async fn invite_id() {
         let actual0 = vec!["1", "91b", "91a", "33", "100"]
             .into_iter()
-            .map(InviteId::parse)
+            .map(|x| InviteId::parse(x).unwrap())
             .sorted()
             .map(|x| format!("{}", x))
             .collect::<Vec<_>>();
         let expect0 = vec!["1", "33", "91a", "91b", "100"]
             .into_iter()
-            .map(|x| format!("{}", x))
+            .map(|x| x.to_string())
             .collect::<Vec<_>>();
 
         assert_eq!(actual0, expect0);
 
-        assert_eq!(format!("{}", InviteId::parse("33")), "33");
-        assert_eq!(format!("{}", InviteId::parse("91a")), "91a");
-        assert_eq!(format!("{}", InviteId::parse("91b")), "91b");
+        assert_eq!(format!("{}", InviteId::parse("33").unwrap()), "33");
+        assert_eq!(format!("{}", InviteId::parse("91a").unwrap()), "91a");
+        assert_eq!(format!("{}", InviteId::parse("91b").unwrap()), "91b");
+        assert!(InviteId::parse("91c").is_err());
     }
 }
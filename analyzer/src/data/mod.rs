@@ -1,43 +1,78 @@
+mod archive;
 mod category;
+mod diff;
 mod invite;
 mod pathway;
 mod pool;
 mod raw;
+mod report;
+mod schema;
+mod source;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+#[cfg(test)]
 use async_once_cell::OnceCell;
-use raw::raw_data;
-use wasm_bindgen::prelude::wasm_bindgen;
 
-pub use category::{Category, CategoryCode};
+pub use archive::{Archive, Snapshot};
+pub use category::{Category, CategoryCode, CategoryRegistry, CategoryRound};
+pub use diff::{SnapshotChange, SnapshotDiff};
 pub use invite::{Invite, InviteId};
-pub use pathway::Pathway;
+pub use pathway::{Pathway, PathwayCode};
 pub use pool::Pool;
+pub use raw::{raw_data, EeRounds123En, RawData};
+pub use report::{DroppedRow, ValidationIssue, ValidationReport};
+pub use schema::BucketSchema;
+pub use source::DataSource;
+#[cfg(feature = "wasm")]
+pub use wasm::*;
 
-#[wasm_bindgen]
-pub async fn wasm_invite_data() -> *const Vec<Invite> {
-    static DATA: OnceCell<Vec<Invite>> = OnceCell::new();
-    return DATA
-        .get_or_init(async { Invite::parse_all(raw_data().await) })
-        .await;
-}
-
-#[wasm_bindgen]
-pub async fn wasm_pool_data() -> *const Vec<Pool> {
-    static DATA: OnceCell<Vec<Pool>> = OnceCell::new();
-    return DATA
-        .get_or_init(async { Pool::parse_all(raw_data().await) })
-        .await;
+#[cfg(test)]
+pub(crate) async fn test_raw_data() -> &'static EeRounds123En {
+    static DATA: OnceCell<EeRounds123En> = OnceCell::new();
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/fixtures/ee_rounds_123_en.json"
+    );
+    DATA.get_or_init(async { raw_data(&DataSource::File(path.into())).await.unwrap() })
+        .await
 }
 
 mod utils {
-    use chrono::NaiveDate;
-    use wasm_bindgen::UnwrapThrowExt;
+    use super::RawData;
+    use crate::error::AnalyzerError;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    pub fn parse_date(
+        raw_data: &RawData,
+        field: &'static str,
+        x: &str,
+    ) -> Result<NaiveDate, AnalyzerError> {
+        NaiveDate::parse_from_str(x, "%B %d, %Y")
+            .map_err(|_| AnalyzerError::field(raw_data, field, x))
+    }
 
-    pub fn parse_date(x: &str) -> NaiveDate {
-        NaiveDate::parse_from_str(x, "%B %d, %Y").unwrap_throw()
+    // e.g. "January 23, 2024 at 14:11:36 UTC", missing for older rounds
+    pub fn parse_date_time(
+        raw_data: &RawData,
+        field: &'static str,
+        x: &str,
+    ) -> Result<Option<NaiveDateTime>, AnalyzerError> {
+        if x.trim().is_empty() {
+            return Ok(None);
+        }
+        NaiveDateTime::parse_from_str(x.trim(), "%B %d, %Y at %H:%M:%S UTC")
+            .map(Some)
+            .map_err(|_| AnalyzerError::field(raw_data, field, x))
     }
 
-    pub fn parse_i32(x: &str) -> i32 {
-        x.replace(",", "").parse().unwrap_throw()
+    pub fn parse_i32(
+        raw_data: &RawData,
+        field: &'static str,
+        x: &str,
+    ) -> Result<i32, AnalyzerError> {
+        x.replace(',', "")
+            .parse()
+            .map_err(|_| AnalyzerError::field(raw_data, field, x))
     }
 }
@@ -1,4 +1,42 @@
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use crate::error::AnalyzerError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(u8)]
+pub enum PathwayCode {
+    Pnp = 0,
+    Cec,
+    Fsw,
+    Fst,
+}
+
+impl PathwayCode {
+    pub const N: usize = 4;
+
+    pub fn values() -> &'static [PathwayCode] {
+        &[Self::Pnp, Self::Cec, Self::Fsw, Self::Fst]
+    }
+
+    pub fn as_str(&self) -> String {
+        match self {
+            Self::Pnp => "PNP".into(),
+            Self::Cec => "CEC".into(),
+            Self::Fsw => "FSW".into(),
+            Self::Fst => "FST".into(),
+        }
+    }
+
+    pub fn as_color(&self) -> String {
+        match self {
+            Self::Pnp => "#9B59B6".into(),
+            Self::Cec => "#E74C3C".into(),
+            Self::Fsw => "#C0392B".into(),
+            Self::Fst => "#7F8C8D".into(),
+        }
+    }
+}
+
+/// The programs eligible for a draw, as a bitmask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Pathway(u32);
 
 impl Pathway {
@@ -23,6 +61,48 @@ impl Pathway {
         (self.0 & Pathway::FST) != 0
     }
 
+    pub fn contains(&self, code: PathwayCode) -> bool {
+        match code {
+            PathwayCode::Pnp => self.is_pnp(),
+            PathwayCode::Cec => self.is_cec(),
+            PathwayCode::Fsw => self.is_fsw(),
+            PathwayCode::Fst => self.is_fst(),
+        }
+    }
+
+    pub fn from_codes(codes: &[PathwayCode]) -> Self {
+        Self(codes.iter().fold(0, |x, code| {
+            x | match code {
+                PathwayCode::Pnp => Pathway::PNP,
+                PathwayCode::Cec => Pathway::CEC,
+                PathwayCode::Fsw => Pathway::FSW,
+                PathwayCode::Fst => Pathway::FST,
+            }
+        }))
+    }
+
+    /// whether a candidate of one of `other` may be invited by this draw
+    pub fn intersects(&self, other: Pathway) -> bool {
+        (self.0 & other.0) != 0
+    }
+
+    pub fn codes(&self) -> Vec<PathwayCode> {
+        PathwayCode::values()
+            .iter()
+            .copied()
+            .filter(|code| self.contains(*code))
+            .collect()
+    }
+
+    /// e.g. "CEC" or "PNP+CEC+FSW+FST"
+    pub fn as_str(&self) -> String {
+        self.codes()
+            .iter()
+            .map(|code| code.as_str())
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
     pub fn parse(raw_data: &str) -> Self {
         let mut x = 0;
         if raw_data.contains("Federal Skilled Worker") {
@@ -44,3 +124,47 @@ impl Pathway {
         self.is_cec() || self.is_fst() || self.is_fsw() || self.is_pnp()
     }
 }
+
+impl std::str::FromStr for Pathway {
+    type Err = AnalyzerError;
+
+    /// e.g. "CEC" or "PNP+CEC", as in `as_str`
+    fn from_str(x: &str) -> Result<Self, Self::Err> {
+        let codes = x
+            .split('+')
+            .map(|code| {
+                PathwayCode::values()
+                    .iter()
+                    .copied()
+                    .find(|value| value.as_str().eq_ignore_ascii_case(code.trim()))
+                    .ok_or(AnalyzerError::Format(format!("invalid pathway {:?}", x)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::from_codes(&codes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pathway, PathwayCode};
+
+    #[test]
+    fn pathway() {
+        let all = Pathway::parse(
+            "Federal Skilled Worker, Canadian Experience Class, Federal Skilled Trades and Provincial Nominee Program",
+        );
+        let cec = Pathway::parse("Canadian Experience Class");
+
+        assert_eq!(all.codes(), PathwayCode::values());
+        assert_eq!(all.as_str(), "PNP+CEC+FSW+FST");
+        assert_eq!(cec.codes(), vec![PathwayCode::Cec]);
+        assert_eq!(cec.as_str(), "CEC");
+        assert!(!Pathway::parse("Skilled Worker").is_valid());
+
+        assert_eq!("PNP+CEC+FSW+FST".parse::<Pathway>().unwrap(), all);
+        assert_eq!("cec".parse::<Pathway>().unwrap(), cec);
+        assert!("CEC+EE".parse::<Pathway>().is_err());
+        assert!(all.intersects(cec));
+        assert!(!cec.intersects(Pathway::from_codes(&[PathwayCode::Pnp])));
+    }
+}
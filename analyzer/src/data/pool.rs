@@ -1,15 +1,23 @@
 use super::raw::{EeRounds123En, RawData};
 use super::utils::{parse_date, parse_i32};
+use super::BucketSchema;
+use crate::error::AnalyzerError;
 use chrono::NaiveDate;
 use itertools::Itertools;
 use std::cmp::Ordering;
 use std::ops::Index;
-use wasm_bindgen::throw_str;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Pool {
-    pub data: [f64; Pool::N],
+    /// the layout of `data`
+    pub schema: &'static BucketSchema,
+    /// counts of the buckets of `schema`, from the lowest score
+    pub data: [f64; BucketSchema::MAX],
     pub date: NaiveDate,
+    /// the date of the draw that published it, a few days after `date`
+    pub published: NaiveDate,
+    /// whether the buckets add up to the subtotals published by IRCC
+    pub consistent: bool,
 }
 
 impl PartialEq for Pool {
@@ -40,144 +48,261 @@ impl Index<usize> for Pool {
 }
 
 impl Pool {
-    pub const N: usize = 15;
+    /// Parse with the schema IRCC used on `drawDistributionAsOn`.
+    pub fn parse(raw_data: &RawData) -> Result<Self, AnalyzerError> {
+        let date = parse_date(
+            raw_data,
+            "drawDistributionAsOn",
+            &raw_data.draw_distribution_as_on,
+        )?;
+        Self::parse_with(raw_data, BucketSchema::for_date(date))
+    }
 
-    pub fn parse(raw_data: &RawData) -> Self {
-        Self {
-            date: parse_date(&raw_data.draw_distribution_as_on),
-            data: [
-                parse_i32(&raw_data.dd17) as f64,
-                parse_i32(&raw_data.dd16) as f64,
-                parse_i32(&raw_data.dd15) as f64,
-                parse_i32(&raw_data.dd14) as f64,
-                parse_i32(&raw_data.dd13) as f64,
-                parse_i32(&raw_data.dd12) as f64,
-                parse_i32(&raw_data.dd11) as f64,
-                parse_i32(&raw_data.dd10) as f64,
-                parse_i32(&raw_data.dd8) as f64,
-                parse_i32(&raw_data.dd7) as f64,
-                parse_i32(&raw_data.dd6) as f64,
-                parse_i32(&raw_data.dd5) as f64,
-                parse_i32(&raw_data.dd4) as f64,
-                parse_i32(&raw_data.dd2) as f64,
-                parse_i32(&raw_data.dd1) as f64,
-            ],
+    pub fn parse_with(
+        raw_data: &RawData,
+        schema: &'static BucketSchema,
+    ) -> Result<Self, AnalyzerError> {
+        let mut pool = Self {
+            schema,
+            date: parse_date(
+                raw_data,
+                "drawDistributionAsOn",
+                &raw_data.draw_distribution_as_on,
+            )?,
+            published: parse_date(raw_data, "drawDateFull", &raw_data.draw_date_full)?,
+            data: [0_f64; BucketSchema::MAX],
+            consistent: true,
+        };
+        for i in 0..schema.len() {
+            let (field, x) = schema.field(raw_data, i);
+            pool.data[i] = parse_i32(raw_data, field, x)? as f64;
         }
+        // a missing or malformed subtotal cannot vouch for the buckets
+        pool.consistent = pool
+            .subtotals(raw_data)
+            .iter()
+            .all(|(_, expect, actual)| expect.as_ref() == Ok(actual));
+        Ok(pool)
+    }
+
+    /// IRCC also publishes subtotals of the buckets, e.g. `dd9` (401 - 450),
+    /// `dd3` (451 - 500) and `dd18` (all) in the current schema, return them
+    /// as (field, published subtotal, sum of buckets).
+    pub fn subtotals(
+        &self,
+        raw_data: &RawData,
+    ) -> Vec<(&'static str, Result<f64, AnalyzerError>, f64)> {
+        self.schema
+            .subtotals
+            .iter()
+            .map(|(field, start, end)| {
+                let x = raw_data.field(field).unwrap_or_default();
+                (
+                    *field,
+                    parse_i32(raw_data, field, x).map(|x| x as f64),
+                    self.data[*start..*end].iter().sum::<f64>(),
+                )
+            })
+            .collect()
     }
 
-    // ensure sorted
-    pub fn parse_all(raw_data: &EeRounds123En) -> Vec<Self> {
-        raw_data
+    // ensure sorted, fails on the first malformed round
+    pub fn parse_all(raw_data: &EeRounds123En) -> Result<Vec<Self>, AnalyzerError> {
+        let pools = raw_data
             .rounds
             .iter()
-            .map(|round| Self::parse(round))
+            .map(Self::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::finalize(pools))
+    }
+
+    // ensure sorted, skips malformed rounds and reports them
+    pub fn parse_all_lenient(raw_data: &EeRounds123En) -> (Vec<Self>, Vec<AnalyzerError>) {
+        let (pools, errors): (Vec<_>, Vec<_>) =
+            raw_data.rounds.iter().map(Self::parse).partition_result();
+        (Self::finalize(pools), errors)
+    }
+
+    // one pool per date, all in the schema of the latest one
+    fn finalize(pools: Vec<Self>) -> Vec<Self> {
+        let pools: Vec<_> = pools
+            .into_iter()
             .filter(|pool| pool.is_valid())
             .sorted()
             .group_by(|pool| pool.date)
             .into_iter()
-            .map(|(_, mut group)| group.nth(0).unwrap())
-            .collect::<Vec<_>>()
+            .map(|(_, group)| {
+                // prefer a snapshot whose buckets match the subtotals
+                let group: Vec<_> = group.collect();
+                group
+                    .iter()
+                    .find(|pool| pool.is_consistent())
+                    .copied()
+                    .unwrap_or(group[0])
+            })
+            .collect();
+
+        let Some(schema) = pools.last().map(|pool| pool.schema) else {
+            return pools;
+        };
+        pools.iter().map(|pool| pool.to_schema(schema)).collect()
+    }
+
+    /// The counts spread over the buckets of `schema`, assuming candidates
+    /// are evenly spread within each bucket.
+    pub fn to_schema(&self, schema: &'static BucketSchema) -> Self {
+        if self.schema == schema {
+            return *self;
+        }
+        Self {
+            schema,
+            data: schema.rebucket(self.schema, self.values()),
+            ..*self
+        }
     }
 
     pub fn is_valid(&self) -> bool {
         self.total() != 0_f64
     }
 
-    pub fn total(&self) -> f64 {
-        self.data.iter().sum()
+    pub fn is_consistent(&self) -> bool {
+        self.consistent
     }
 
-    pub fn count(&self, i: usize) -> f64 {
-        self.data[i]
+    pub fn total(&self) -> f64 {
+        self.values().iter().sum()
     }
 
-    pub fn min_score(i: usize) -> i64 {
-        match i {
-            0 => 0,
-            1 => 300,
-            2 => 350,
-            3 => 400,
-            4 => 410,
-            5 => 420,
-            6 => 430,
-            7 => 440,
-            8 => 450,
-            9 => 460,
-            10 => 470,
-            11 => 480,
-            12 => 490,
-            13 => 500,
-            14 => 600,
-            _ => throw_str("index out of bound"),
-        }
+    pub fn len(&self) -> usize {
+        self.schema.len()
     }
 
-    pub fn max_score(i: usize) -> i64 {
-        match i {
-            0 => 300,
-            1 => 350,
-            2 => 400,
-            3 => 410,
-            4 => 420,
-            5 => 430,
-            6 => 440,
-            7 => 450,
-            8 => 460,
-            9 => 470,
-            10 => 480,
-            11 => 490,
-            12 => 500,
-            13 => 600,
-            14 => 1200,
-            _ => throw_str("index out of bound"),
-        }
+    pub fn is_empty(&self) -> bool {
+        self.schema.is_empty()
     }
 
-    pub fn as_color(i: usize) -> String {
-        let p = i as f64 / 15_f64;
-        const P: f64 = 0.6;
-
-        let r: i32;
-        let g: i32;
+    /// the counts of the buckets of `schema`
+    pub fn values(&self) -> &[f64] {
+        &self.data[..self.len()]
+    }
 
-        if p < P {
-            r = 255;
-            g = f64::floor((p / P) * 255.0) as i32;
-        } else {
-            r = 255 - f64::floor(((p - P) / (1.0 - P)) * 255.0) as i32;
-            g = 255;
-        }
-        format!("#{:02x}{:02x}00", r, g)
+    pub fn count(&self, i: usize) -> f64 {
+        self.data[i]
     }
 
-    pub fn as_str(i: usize) -> String {
-        format!(
-            "{} - {}",
-            if Pool::min_score(i) == 0 {
-                0
-            } else {
-                Pool::min_score(i) + 1
-            },
-            Pool::max_score(i)
-        )
+    /// The schema of a timeline from `parse_all`, the latest one if it is empty.
+    pub fn schema_of(pools: &[Self]) -> &'static BucketSchema {
+        pools
+            .last()
+            .map(|pool| pool.schema)
+            .unwrap_or(BucketSchema::latest())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::super::raw::raw_data;
+    use super::super::test_raw_data;
     use super::Pool;
+    use crate::data::BucketSchema;
+    use crate::error::AnalyzerError;
+    use chrono::NaiveDate;
 
     #[tokio::test]
     async fn parse_data() {
-        let x = raw_data().await;
-        let p = Pool::parse_all(&x);
+        let x = test_raw_data().await;
+        let p = Pool::parse_all(x).unwrap();
 
         assert!(p.iter().all(|x| x.is_valid()));
     }
 
     #[tokio::test]
-    async fn color() {
-        assert_eq!("#ff8d00", Pool::as_color(5))
+    async fn parse_error() {
+        let mut x = test_raw_data().await.clone();
+        x.rounds[0].draw_distribution_as_on = "2024-06-17".into();
+        x.rounds[1].dd9 = "n/a".into();
+        x.rounds[2].dd4 = "1.234".into();
+
+        assert!(Pool::parse_all(&x).is_err());
+
+        // the subtotal dd9 only makes its pool inconsistent
+        let pool = Pool::parse(&x.rounds[1]).unwrap();
+        assert!(!pool.is_consistent());
+        assert!(matches!(
+            pool.subtotals(&x.rounds[1])[0].1,
+            Err(AnalyzerError::Field { field: "dd9", .. })
+        ));
+
+        let (pools, errors) = Pool::parse_all_lenient(&x);
+        assert!(pools
+            .iter()
+            .any(|x| x.date == pool.date && !x.is_consistent()));
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            AnalyzerError::Field {
+                field: "drawDistributionAsOn",
+                ..
+            }
+        ));
+        assert!(matches!(
+            &errors[1],
+            AnalyzerError::Field { field: "dd4", .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn consistent() {
+        let x = test_raw_data().await;
+        let round = x.rounds.iter().find(|x| x.draw_number == "271").unwrap();
+        let pool = Pool::parse(round).unwrap();
+        assert!(!pool.is_consistent());
+        assert_eq!(
+            pool.subtotals(round)
+                .iter()
+                .map(|(_, x, y)| x.as_ref().unwrap() - y)
+                .collect::<Vec<_>>(),
+            [1000.0, 0.0, 0.0]
+        );
+
+        let p = Pool::parse_all(x).unwrap();
+        assert_eq!(p.iter().filter(|x| !x.is_consistent()).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn schema() {
+        // the rounds before 2024 read as if published in the coarse layout
+        let x = test_raw_data().await;
+        let split = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let pools: Vec<_> = x
+            .rounds
+            .iter()
+            .map(|round| {
+                let pool = Pool::parse(round).unwrap();
+                if pool.date < split {
+                    Pool::parse_with(round, &BucketSchema::COARSE).unwrap()
+                } else {
+                    pool
+                }
+            })
+            .collect();
+        assert!(pools.iter().any(|pool| pool.len() == 7));
+        // the bad dd9 of #271 is a bucket here, caught by dd18 instead
+        assert_eq!(pools.iter().filter(|x| !x.is_consistent()).count(), 1);
+
+        let p = Pool::finalize(pools);
+        assert!(p.iter().all(|pool| pool.schema == &BucketSchema::CURRENT));
+        assert_eq!(Pool::schema_of(&p), &BucketSchema::CURRENT);
+
+        let pool = p.first().unwrap();
+        let round = x
+            .rounds
+            .iter()
+            .rfind(|round| Pool::parse(round).unwrap().date == pool.date)
+            .unwrap();
+        let dd9: f64 = round.dd9.replace(',', "").parse().unwrap();
+        assert!(pool.values()[3..8]
+            .iter()
+            .all(|x| (x - dd9 / 5.0).abs() < 1e-6));
+        assert!((pool.total() - Pool::parse(round).unwrap().total()).abs() < 1e-6);
     }
 }
@@ -1,8 +1,6 @@
-extern crate wasm_bindgen;
-
-use async_once_cell::OnceCell;
+use super::DataSource;
+use crate::error::AnalyzerError;
 use serde::Deserialize;
-use wasm_bindgen::prelude::*;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct EeRounds123En {
@@ -57,27 +55,44 @@ pub struct RawData {
     pub mitext: String,
 }
 
-async fn impl_raw_data() -> EeRounds123En {
-    reqwest::get("https://www.canada.ca/content/dam/ircc/documents/json/ee_rounds_123_en.json")
-        .await
-        .unwrap_throw()
-        .json::<EeRounds123En>()
-        .await
-        .unwrap_throw()
+impl RawData {
+    /// A `dd` field by name, as referred to by `BucketSchema`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        match name {
+            "dd1" => Some(&self.dd1),
+            "dd2" => Some(&self.dd2),
+            "dd3" => Some(&self.dd3),
+            "dd4" => Some(&self.dd4),
+            "dd5" => Some(&self.dd5),
+            "dd6" => Some(&self.dd6),
+            "dd7" => Some(&self.dd7),
+            "dd8" => Some(&self.dd8),
+            "dd9" => Some(&self.dd9),
+            "dd10" => Some(&self.dd10),
+            "dd11" => Some(&self.dd11),
+            "dd12" => Some(&self.dd12),
+            "dd13" => Some(&self.dd13),
+            "dd14" => Some(&self.dd14),
+            "dd15" => Some(&self.dd15),
+            "dd16" => Some(&self.dd16),
+            "dd17" => Some(&self.dd17),
+            "dd18" => Some(&self.dd18),
+            _ => None,
+        }
+    }
 }
 
-pub async fn raw_data() -> &'static EeRounds123En {
-    static DATA: OnceCell<EeRounds123En> = OnceCell::new();
-    return DATA.get_or_init(impl_raw_data()).await;
+pub async fn raw_data(source: &DataSource) -> Result<EeRounds123En, AnalyzerError> {
+    source.load().await
 }
 
 #[cfg(test)]
 mod tests {
-    use super::raw_data;
+    use super::super::test_raw_data;
 
     #[tokio::test]
     async fn parse_data() {
-        let x = raw_data().await;
+        let x = test_raw_data().await;
         println!("{:?}", x);
     }
 }
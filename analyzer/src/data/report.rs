@@ -0,0 +1,324 @@
+use super::raw::{EeRounds123En, RawData};
+use super::{Invite, Pool};
+use crate::error::AnalyzerError;
+use chrono::NaiveDate;
+use itertools::Itertools;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ValidationIssue {
+    /// a field of the round cannot be parsed, see `AnalyzerError`
+    Malformed { draw: String, message: String },
+    /// `drawName` does not map to any known category, kept as "Other: <name>"
+    UnknownCategory { draw: String, name: String },
+    /// `drawText2` does not mention any known pathway
+    UnknownPathway { draw: String, text: String },
+    /// buckets do not add up to a subtotal published by IRCC
+    InconsistentPool {
+        draw: String,
+        field: String,
+        expect: i64,
+        actual: i64,
+    },
+    /// rounds share a `drawDistributionAsOn` date but not the same counts
+    ConflictingPool { date: String, draws: Vec<String> },
+    /// a later draw has a smaller (or the same) draw number
+    NonMonotonicDraw { draw: String, previous: String },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Malformed { message, .. } => message.fmt(f),
+            Self::UnknownCategory { draw, name } => {
+                write!(f, "draw {}: unknown category {:?}", draw, name)
+            }
+            Self::UnknownPathway { draw, text } => {
+                write!(f, "draw {}: unknown pathway {:?}", draw, text)
+            }
+            Self::InconsistentPool {
+                draw,
+                field,
+                expect,
+                actual,
+            } => write!(
+                f,
+                "draw {}: {} is {} but buckets add up to {}",
+                draw, field, expect, actual
+            ),
+            Self::ConflictingPool { date, draws } => write!(
+                f,
+                "pool on {}: conflicting counts in draws {}",
+                date,
+                draws.join(", ")
+            ),
+            Self::NonMonotonicDraw { draw, previous } => {
+                write!(f, "draw {}: not after draw {}", draw, previous)
+            }
+        }
+    }
+}
+
+/// A row left out of the lenient parse.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DroppedRow {
+    /// a draw which is not part of `Invite::parse_all_lenient`
+    Invite { draw: String },
+    /// the distribution of a draw which is not part of
+    /// `Pool::parse_all_lenient`, for the `field` that cannot be parsed
+    Pool { draw: String, field: String },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub dropped: Vec<DroppedRow>,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn new(raw_data: &EeRounds123En) -> Self {
+        let mut report = Self::default();
+        let mut invites = Vec::with_capacity(raw_data.rounds.len());
+        let mut pools = Vec::with_capacity(raw_data.rounds.len());
+
+        for round in raw_data.rounds.iter() {
+            let draw = &round.draw_number;
+            match Invite::parse(round) {
+                Err(error) => {
+                    report
+                        .dropped
+                        .push(DroppedRow::Invite { draw: draw.clone() });
+                    report.issues.push(ValidationIssue::Malformed {
+                        draw: draw.clone(),
+                        message: error.to_string(),
+                    });
+                }
+                Ok(invite) => {
+                    if !invite.category.is_known() {
+                        report.issues.push(ValidationIssue::UnknownCategory {
+                            draw: draw.clone(),
+                            name: round.draw_name.clone(),
+                        });
+                    }
+                    if !invite.pathway.is_valid() {
+                        report.issues.push(ValidationIssue::UnknownPathway {
+                            draw: draw.clone(),
+                            text: round.draw_text2.clone(),
+                        });
+                    }
+                    if invite.is_valid() {
+                        invites.push(invite);
+                    } else {
+                        report
+                            .dropped
+                            .push(DroppedRow::Invite { draw: draw.clone() });
+                    }
+                }
+            }
+
+            match Pool::parse(round) {
+                Err(error) => {
+                    let field = match &error {
+                        AnalyzerError::Field { field, .. } => field.to_string(),
+                        _ => String::new(),
+                    };
+                    report.dropped.push(DroppedRow::Pool {
+                        draw: draw.clone(),
+                        field,
+                    });
+                    report.issues.push(ValidationIssue::Malformed {
+                        draw: draw.clone(),
+                        message: error.to_string(),
+                    });
+                }
+                Ok(pool) if pool.is_valid() => {
+                    report.check_subtotals(round, &pool);
+                    pools.push((draw.clone(), pool));
+                }
+                Ok(_) => (),
+            }
+        }
+
+        report.check_pools(pools);
+        report.check_invites(invites);
+        report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dropped.is_empty() && self.issues.is_empty()
+    }
+
+    fn check_subtotals(&mut self, round: &RawData, pool: &Pool) {
+        for (field, expect, actual) in pool.subtotals(round) {
+            match expect {
+                // the pool is kept, but inconsistent
+                Err(error) => self.issues.push(ValidationIssue::Malformed {
+                    draw: round.draw_number.clone(),
+                    message: error.to_string(),
+                }),
+                Ok(expect) if expect != actual => {
+                    self.issues.push(ValidationIssue::InconsistentPool {
+                        draw: round.draw_number.clone(),
+                        field: field.into(),
+                        expect: expect as i64,
+                        actual: actual as i64,
+                    })
+                }
+                Ok(_) => (),
+            }
+        }
+    }
+
+    fn check_pools(&mut self, mut pools: Vec<(String, Pool)>) {
+        pools.sort_by_key(|(_, pool)| pool.date);
+        for (date, group) in pools.iter().group_by(|(_, pool)| pool.date).into_iter() {
+            let group: Vec<_> = group.collect();
+            if group.iter().map(|(_, pool)| pool.data).all_equal() {
+                continue;
+            }
+
+            self.issues.push(ValidationIssue::ConflictingPool {
+                date: format_date(date),
+                draws: group.iter().map(|(draw, _)| draw.clone()).collect(),
+            });
+        }
+    }
+
+    fn check_invites(&mut self, mut invites: Vec<Invite>) {
+        // draws on the same day may be published in any order
+        invites.sort_by_key(|invite| (invite.date, invite.id));
+        for (previous, invite) in invites.iter().tuple_windows() {
+            if previous.id >= invite.id {
+                self.issues.push(ValidationIssue::NonMonotonicDraw {
+                    draw: invite.id.to_string(),
+                    previous: previous.id.to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{test_raw_data, EeRounds123En};
+    use super::{DroppedRow, ValidationIssue, ValidationReport};
+
+    fn report_dd9(x: &EeRounds123En) -> i64 {
+        let round = x.rounds.iter().find(|x| x.draw_number == "271").unwrap();
+        round.dd9.replace(',', "").parse().unwrap()
+    }
+
+    /// the distributions published twice on the same date with different data
+    fn conflicts() -> Vec<ValidationIssue> {
+        [
+            ("2023-07-06", ["256", "255"]),
+            ("2023-08-31", ["268", "267"]),
+            ("2023-09-02", ["270", "269"]),
+            ("2023-09-08", ["273", "272"]),
+            ("2023-10-03", ["279", "278"]),
+            ("2023-11-02", ["286", "285"]),
+            ("2024-05-12", ["301", "300"]),
+        ]
+        .into_iter()
+        .map(|(date, draws)| ValidationIssue::ConflictingPool {
+            date: date.into(),
+            draws: draws.map(String::from).to_vec(),
+        })
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn validate() {
+        let x = test_raw_data().await;
+        let report = ValidationReport::new(x);
+
+        assert!(report.dropped.is_empty());
+        assert_eq!(
+            report.issues,
+            vec![
+                ValidationIssue::UnknownCategory {
+                    draw: "305".into(),
+                    name: "Education occupations (2024-1)".into()
+                },
+                ValidationIssue::InconsistentPool {
+                    draw: "271".into(),
+                    field: "dd9".into(),
+                    expect: report_dd9(x),
+                    actual: report_dd9(x) - 1000,
+                },
+            ]
+            .into_iter()
+            .chain(conflicts())
+            .collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn validate_error() {
+        let mut x = test_raw_data().await.clone();
+        x.rounds[0].draw_size = "".into();
+        x.rounds[2].draw_text2 = "Skilled Worker".into();
+        x.rounds[3].dd18 = "".into();
+        x.rounds[4].dd1 = "n/a".into();
+        x.rounds[5].draw_date_full = "June 20, 2024".into();
+        let report = ValidationReport::new(&x);
+
+        let draw = |i: usize| x.rounds[i].draw_number.clone();
+        assert_eq!(
+            report.dropped,
+            vec![
+                DroppedRow::Invite { draw: draw(0) },
+                DroppedRow::Invite { draw: draw(2) },
+                DroppedRow::Pool {
+                    draw: draw(4),
+                    field: "dd1".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            report.issues,
+            vec![
+                ValidationIssue::Malformed {
+                    draw: draw(0),
+                    message: format!("draw {}: invalid drawSize \"\"", draw(0)),
+                },
+                ValidationIssue::UnknownCategory {
+                    draw: draw(1),
+                    name: "Education occupations (2024-1)".into()
+                },
+                ValidationIssue::UnknownPathway {
+                    draw: draw(2),
+                    text: "Skilled Worker".into(),
+                },
+                // reported, but the pool is kept
+                ValidationIssue::Malformed {
+                    draw: draw(3),
+                    message: format!("draw {}: invalid dd18 \"\"", draw(3)),
+                },
+                ValidationIssue::Malformed {
+                    draw: draw(4),
+                    message: format!("draw {}: invalid dd1 \"n/a\"", draw(4)),
+                },
+                ValidationIssue::InconsistentPool {
+                    draw: "271".into(),
+                    field: "dd9".into(),
+                    expect: report_dd9(&x),
+                    actual: report_dd9(&x) - 1000,
+                },
+            ]
+            .into_iter()
+            .chain(conflicts())
+            .chain([ValidationIssue::NonMonotonicDraw {
+                draw: draw(5),
+                previous: draw(1),
+            }])
+            .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use super::RawData;
+use chrono::NaiveDate;
+
+/// The CRS score ranges of a pool distribution, and the `dd` fields IRCC
+/// publishes them in.
+///
+/// Each layout is kept with the date of the first distribution using it, see
+/// `for_date`. Only the current layout is known so far, it applies to every
+/// date.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BucketSchema {
+    /// the first distribution date this schema applies to
+    pub since: NaiveDate,
+    /// (min, max] score of each bucket, from the lowest
+    pub ranges: &'static [(i64, i64)],
+    /// the field holding the count of each bucket
+    pub fields: &'static [&'static str],
+    /// fields published as the sum of the buckets start..end
+    pub subtotals: &'static [(&'static str, usize, usize)],
+}
+
+impl BucketSchema {
+    /// capacity for the buckets of any schema
+    pub const MAX: usize = 24;
+
+    pub const CURRENT: BucketSchema = BucketSchema {
+        since: NaiveDate::MIN,
+        ranges: &[
+            (0, 300),
+            (300, 350),
+            (350, 400),
+            (400, 410),
+            (410, 420),
+            (420, 430),
+            (430, 440),
+            (440, 450),
+            (450, 460),
+            (460, 470),
+            (470, 480),
+            (480, 490),
+            (490, 500),
+            (500, 600),
+            (600, 1200),
+        ],
+        fields: &[
+            "dd17", "dd16", "dd15", "dd14", "dd13", "dd12", "dd11", "dd10", "dd8", "dd7", "dd6",
+            "dd5", "dd4", "dd2", "dd1",
+        ],
+        subtotals: &[("dd9", 3, 8), ("dd3", 8, 13), ("dd18", 0, 15)],
+    };
+
+    /// Known schemas, oldest first, only the current one so far. Add one here,
+    /// with its `since` date, when IRCC changes the ranges.
+    const ALL: &'static [BucketSchema] = &[Self::CURRENT];
+
+    /// A made up layout with 401 - 450 and 451 - 500 as single ranges, to
+    /// test a change of layout.
+    #[cfg(test)]
+    pub(crate) const COARSE: BucketSchema = BucketSchema {
+        since: NaiveDate::MIN,
+        ranges: &[
+            (0, 300),
+            (300, 350),
+            (350, 400),
+            (400, 450),
+            (450, 500),
+            (500, 600),
+            (600, 1200),
+        ],
+        fields: &["dd17", "dd16", "dd15", "dd9", "dd3", "dd2", "dd1"],
+        subtotals: &[("dd18", 0, 7)],
+    };
+
+    /// The schema of the distributions published on `date`.
+    pub fn for_date(date: NaiveDate) -> &'static Self {
+        Self::select(Self::ALL, date)
+    }
+
+    /// The schema of the distributions published since then.
+    pub fn latest() -> &'static Self {
+        Self::ALL.last().unwrap()
+    }
+
+    /// The latest of `schemas` starting on or before `date`, the oldest if
+    /// `date` predates all of them.
+    pub fn select(schemas: &'static [Self], date: NaiveDate) -> &'static Self {
+        schemas
+            .iter()
+            .take_while(|schema| schema.since <= date)
+            .last()
+            .unwrap_or(&schemas[0])
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn min_score(&self, i: usize) -> i64 {
+        self.ranges[i].0
+    }
+
+    pub fn max_score(&self, i: usize) -> i64 {
+        self.ranges[i].1
+    }
+
+    /// The bucket holding `score`, the lowest or highest one for scores
+    /// out of all ranges.
+    pub fn bucket_of(&self, score: f64) -> usize {
+        (0..self.len())
+            .find(|i| score <= self.max_score(*i) as f64)
+            .unwrap_or(self.len() - 1)
+    }
+
+    /// Share of bucket `i` between `min_score` and `max_score`, assuming the
+    /// candidates spread evenly over the range of the bucket.
+    pub fn fraction_within(&self, i: usize, min_score: f64, max_score: f64) -> f64 {
+        let min_d = self.min_score(i) as f64;
+        let max_d = self.max_score(i) as f64;
+
+        let min_n = f64::min(max_d, f64::max(min_score, min_d));
+        let max_n = f64::max(min_d, f64::min(max_score, max_d));
+
+        (max_n - min_n) / (max_d - min_d)
+    }
+
+    /// Counts of `data`, laid out in `from`, spread over the buckets of this
+    /// schema.
+    pub fn rebucket(&self, from: &Self, data: &[f64]) -> [f64; Self::MAX] {
+        let mut value = [0_f64; Self::MAX];
+        for (i, x) in value.iter_mut().take(self.len()).enumerate() {
+            *x = (0..from.len())
+                .map(|j| {
+                    data[j]
+                        * from.fraction_within(
+                            j,
+                            self.min_score(i) as f64,
+                            self.max_score(i) as f64,
+                        )
+                })
+                .sum();
+        }
+        value
+    }
+
+    pub fn field<'a>(&self, raw_data: &'a RawData, i: usize) -> (&'static str, &'a str) {
+        let field = self.fields[i];
+        (field, raw_data.field(field).unwrap_or_default())
+    }
+
+    pub fn as_color(&self, i: usize) -> String {
+        let p = i as f64 / self.len() as f64;
+        const P: f64 = 0.6;
+
+        let r: i32;
+        let g: i32;
+
+        if p < P {
+            r = 255;
+            g = f64::floor((p / P) * 255.0) as i32;
+        } else {
+            r = 255 - f64::floor(((p - P) / (1.0 - P)) * 255.0) as i32;
+            g = 255;
+        }
+        format!("#{:02x}{:02x}00", r, g)
+    }
+
+    pub fn as_str(&self, i: usize) -> String {
+        format!(
+            "{} - {}",
+            if self.min_score(i) == 0 {
+                0
+            } else {
+                self.min_score(i) + 1
+            },
+            self.max_score(i)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BucketSchema;
+    use chrono::NaiveDate;
+
+    const SPLIT: BucketSchema = BucketSchema {
+        since: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        ..BucketSchema::CURRENT
+    };
+    static SCHEMAS: [BucketSchema; 2] = [BucketSchema::COARSE, SPLIT];
+
+    #[test]
+    fn select() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(
+            *BucketSchema::select(&SCHEMAS, date(2023, 12, 31)),
+            BucketSchema::COARSE
+        );
+        assert_eq!(*BucketSchema::select(&SCHEMAS, date(2024, 1, 1)), SPLIT);
+        assert_eq!(
+            *BucketSchema::select(&SCHEMAS, NaiveDate::MIN),
+            BucketSchema::COARSE
+        );
+        assert_eq!(
+            *BucketSchema::for_date(date(2024, 6, 1)),
+            BucketSchema::CURRENT
+        );
+    }
+
+    #[test]
+    fn rebucket() {
+        let data = [1.0, 2.0, 3.0, 50.0, 40.0, 6.0, 7.0];
+        let value = BucketSchema::CURRENT.rebucket(&BucketSchema::COARSE, &data);
+
+        assert_eq!(
+            value[..BucketSchema::CURRENT.len()],
+            [1.0, 2.0, 3.0, 10.0, 10.0, 10.0, 10.0, 10.0, 8.0, 8.0, 8.0, 8.0, 8.0, 6.0, 7.0]
+        );
+        assert_eq!(
+            BucketSchema::COARSE.rebucket(&BucketSchema::CURRENT, &value)[..7],
+            data
+        );
+        assert_eq!(BucketSchema::CURRENT.as_str(3), "401 - 410");
+        assert_eq!(BucketSchema::CURRENT.bucket_of(0.0), 0);
+        assert_eq!(BucketSchema::CURRENT.bucket_of(410.0), 3);
+        assert_eq!(BucketSchema::CURRENT.bucket_of(411.0), 4);
+        assert_eq!(BucketSchema::CURRENT.bucket_of(1300.0), 14);
+        assert_eq!(BucketSchema::CURRENT.as_color(5), "#ff8d00");
+    }
+}
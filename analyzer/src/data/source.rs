@@ -0,0 +1,88 @@
+use super::raw::EeRounds123En;
+use crate::error::AnalyzerError;
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+/// Where the IRCC `ee_rounds_123_en.json` document is loaded from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSource {
+    /// fetch the document over http(s)
+    Url(String),
+    /// read the document from a local file, e.g. an archived snapshot
+    File(PathBuf),
+    /// the document already held in memory
+    Bytes(Vec<u8>),
+    /// the document as a string, e.g. provided by the JS side
+    Text(String),
+}
+
+impl Default for DataSource {
+    fn default() -> Self {
+        Self::Url(Self::IRCC_URL.into())
+    }
+}
+
+impl DataSource {
+    pub const IRCC_URL: &'static str =
+        "https://www.canada.ca/content/dam/ircc/documents/json/ee_rounds_123_en.json";
+
+    pub async fn load(&self) -> Result<EeRounds123En, AnalyzerError> {
+        let bytes = self.bytes().await?;
+        serde_json::from_slice(&bytes).map_err(|e| AnalyzerError::Format(e.to_string()))
+    }
+
+    /// The document as published, before it is parsed.
+    pub async fn bytes(&self) -> Result<Cow<'_, [u8]>, AnalyzerError> {
+        fn source_err(e: impl std::fmt::Display) -> AnalyzerError {
+            AnalyzerError::Source(e.to_string())
+        }
+
+        match self {
+            Self::Url(url) => {
+                let response = reqwest::get(url).await.map_err(source_err)?;
+                let bytes = response.bytes().await.map_err(source_err)?;
+                Ok(Cow::Owned(bytes.into()))
+            }
+            Self::File(path) => Ok(Cow::Owned(std::fs::read(path).map_err(source_err)?)),
+            Self::Bytes(bytes) => Ok(Cow::Borrowed(bytes)),
+            Self::Text(text) => Ok(Cow::Borrowed(text.as_bytes())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataSource;
+    use crate::error::AnalyzerError;
+
+    #[tokio::test]
+    async fn load() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/fixtures/ee_rounds_123_en.json"
+        );
+        let text = std::fs::read_to_string(path).unwrap();
+
+        let x0 = DataSource::File(path.into()).load().await.unwrap();
+        let x1 = DataSource::Bytes(text.clone().into_bytes())
+            .load()
+            .await
+            .unwrap();
+        let x2 = DataSource::Text(text).load().await.unwrap();
+
+        assert!(!x0.rounds.is_empty());
+        assert_eq!(x0.rounds.len(), x1.rounds.len());
+        assert_eq!(x0.rounds.len(), x2.rounds.len());
+    }
+
+    #[tokio::test]
+    async fn load_error() {
+        let x0 = DataSource::File("/nonexistent/ee_rounds_123_en.json".into())
+            .load()
+            .await;
+        let x1 = DataSource::Text("{\"rounds\": 1}".into()).load().await;
+
+        assert!(matches!(x0, Err(AnalyzerError::Source(_))));
+        assert!(matches!(x1, Err(AnalyzerError::Format(_))));
+    }
+}
@@ -0,0 +1,102 @@
+use super::{
+    raw_data, CategoryRegistry, DataSource, EeRounds123En, Invite, Pool, ValidationReport,
+};
+use crate::error::AnalyzerError;
+use crate::utils::console_log;
+use async_once_cell::OnceCell;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::{wasm_bindgen, JsError, JsValue};
+
+/// Invitations handed to JS, the invitation charts are its methods.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct InviteSet(Arc<Vec<Invite>>);
+
+impl Deref for InviteSet {
+    type Target = [Invite];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Pool distributions handed to JS, the pool charts are its methods.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct PoolSet(Arc<Vec<Pool>>);
+
+impl Deref for PoolSet {
+    type Target = [Pool];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+static WASM_SOURCE: Mutex<Option<DataSource>> = Mutex::new(None);
+
+// only takes effect if called before the first `wasm_invite_data` or `wasm_pool_data`.
+#[wasm_bindgen]
+pub fn wasm_use_data_url(url: String) {
+    *WASM_SOURCE.lock().unwrap() = Some(DataSource::Url(url));
+}
+
+// only takes effect if called before the first `wasm_invite_data` or `wasm_pool_data`.
+#[wasm_bindgen]
+pub fn wasm_use_data_text(text: String) {
+    *WASM_SOURCE.lock().unwrap() = Some(DataSource::Text(text));
+}
+
+// a category config as in `categories.json`, only takes effect if called before
+// the first `wasm_invite_data`.
+#[wasm_bindgen]
+pub fn wasm_use_categories(text: String) -> Result<(), JsError> {
+    CategoryRegistry::from_json(&text)?.install();
+    Ok(())
+}
+
+async fn wasm_raw_data() -> Result<&'static EeRounds123En, AnalyzerError> {
+    static DATA: OnceCell<EeRounds123En> = OnceCell::new();
+    DATA.get_or_try_init(async {
+        let source = WASM_SOURCE.lock().unwrap().clone().unwrap_or_default();
+        raw_data(&source).await
+    })
+    .await
+}
+
+#[wasm_bindgen]
+pub async fn wasm_invite_data() -> Result<InviteSet, JsError> {
+    static DATA: OnceCell<Arc<Vec<Invite>>> = OnceCell::new();
+    let data = DATA
+        .get_or_try_init(async {
+            let (data, errors) = Invite::parse_all_lenient(wasm_raw_data().await?);
+            errors
+                .iter()
+                .for_each(|error| console_log!("skip invite: {}", error));
+            Ok::<_, AnalyzerError>(Arc::new(data))
+        })
+        .await?;
+    Ok(InviteSet(data.clone()))
+}
+
+#[wasm_bindgen]
+pub async fn wasm_pool_data() -> Result<PoolSet, JsError> {
+    static DATA: OnceCell<Arc<Vec<Pool>>> = OnceCell::new();
+    let data = DATA
+        .get_or_try_init(async {
+            let (data, errors) = Pool::parse_all_lenient(wasm_raw_data().await?);
+            errors
+                .iter()
+                .for_each(|error| console_log!("skip pool: {}", error));
+            Ok::<_, AnalyzerError>(Arc::new(data))
+        })
+        .await?;
+    Ok(PoolSet(data.clone()))
+}
+
+#[wasm_bindgen]
+pub async fn wasm_validation_report() -> Result<JsValue, JsError> {
+    let report = ValidationReport::new(wasm_raw_data().await?);
+    Ok(serde_wasm_bindgen::to_value(&report)?)
+}
@@ -0,0 +1,39 @@
+use crate::data::RawData;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalyzerError {
+    /// the raw data cannot be fetched or read from its source
+    Source(String),
+    /// the raw data is not a valid IRCC document
+    Format(String),
+    /// a field of a round cannot be parsed
+    Field {
+        draw: String,
+        field: &'static str,
+        value: String,
+    },
+}
+
+impl AnalyzerError {
+    pub fn field(raw_data: &RawData, field: &'static str, value: &str) -> Self {
+        Self::Field {
+            draw: raw_data.draw_number.clone(),
+            field,
+            value: value.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Source(message) => write!(f, "cannot load raw data: {}", message),
+            Self::Format(message) => write!(f, "invalid raw data: {}", message),
+            Self::Field { draw, field, value } => {
+                write!(f, "draw {}: invalid {} {:?}", draw, field, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalyzerError {}
@@ -1,7 +1,10 @@
-mod analyze;
+pub mod analyze;
+#[cfg(feature = "wasm")]
 pub mod chart;
 pub mod data;
+pub mod error;
 
+#[cfg(feature = "wasm")]
 #[allow(unused_imports, unused_macros)]
 pub(crate) mod utils {
     use wasm_bindgen::prelude::*;
@@ -56,18 +56,3 @@ export function useCategoryColor(str: DrawCategory): string {
         return "#000000";
     }
 }
-
-export let IrccPlan = {
-    2015: { min: 68000, max: 74000 },
-    2016: { min: 54000, max: 59000 },
-    2017: { min: 69600, max: 77300 },
-    2018: { min: 72700, max: 78200 },
-    2019: { min: 76000, max: 86000 },
-    2020: { min: 88500, max: 100000 },
-    2021: { min: 81000, max: 110250 },
-    2022: { min: 52000, max: 64000 },
-    2023: { min: 67750, max: 88000 },
-    2024: { min: 90000, max: 116000 },
-    2025: { min: 96500, max: 124000 },
-    2026: { min: 96500, max: 124000 },
-};